@@ -1 +1,50 @@
-import("../pkg/index.js").catch(console.error);
+import("../pkg/index.js").then(module => module.main_js()).catch(console.error);
+
+// The service worker only exists in release builds, see OfflinePlugin in
+// webpack.config.js.
+if ("serviceWorker" in navigator) {
+  window.addEventListener("load", () => {
+    navigator.serviceWorker.register("sw.js").then(registration => {
+      if (registration.waiting && navigator.serviceWorker.controller) {
+        showUpdateNotice(registration.waiting);
+      }
+      registration.addEventListener("updatefound", () => {
+        const worker = registration.installing;
+        worker.addEventListener("statechange", () => {
+          // Without a controller this is the first install, not an update.
+          if (worker.state === "installed" && navigator.serviceWorker.controller) {
+            showUpdateNotice(worker);
+          }
+        });
+      });
+    }).catch(error => console.log("Offline mode not available", error));
+
+    let reloading = false;
+    navigator.serviceWorker.addEventListener("controllerchange", () => {
+      if (!reloading) {
+        reloading = true;
+        window.location.reload();
+      }
+    });
+  });
+}
+
+function showUpdateNotice(worker) {
+  if (document.querySelector(".update-notice")) {
+    return;
+  }
+  const notice = document.createElement("div");
+  notice.className = "update-notice";
+  notice.textContent = "A new version is available.";
+
+  const reload = document.createElement("button");
+  reload.textContent = "Reload";
+  reload.addEventListener("click", () => worker.postMessage({ type: "skipWaiting" }));
+  const dismiss = document.createElement("button");
+  dismiss.className = "mdi-set mdi-close";
+  dismiss.title = "Later";
+  dismiss.addEventListener("click", () => notice.remove());
+
+  notice.append(reload, dismiss);
+  document.body.append(notice);
+}
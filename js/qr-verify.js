@@ -0,0 +1,54 @@
+// Defines <qr-verify trusted-keys="..."> for embedding the verifier into other
+// pages. See README.md for the attributes and events.
+const wasm = import("../pkg/index.js");
+
+// Missing or invalid thresholds require a single signature.
+function threshold(value) {
+  const number = parseInt(value, 10);
+  return number > 0 ? number : 1;
+}
+
+class QrVerify extends HTMLElement {
+  static get observedAttributes() {
+    return ["trusted-keys", "kiosk", "cosigner-keys", "threshold"];
+  }
+
+  connectedCallback() {
+    wasm.then(module => {
+      if (this.isConnected && !this.handle) {
+        this.handle = module.mount_verifier(
+          this,
+          this.getAttribute("trusted-keys") || "",
+          this.hasAttribute("kiosk"),
+          this.getAttribute("cosigner-keys") || "",
+          threshold(this.getAttribute("threshold")),
+        );
+      }
+    }).catch(console.error);
+  }
+
+  disconnectedCallback() {
+    if (this.handle) {
+      this.handle.destroy();
+      this.handle.free();
+      this.handle = null;
+    }
+  }
+
+  attributeChangedCallback(name, oldValue, newValue) {
+    if (!this.handle) {
+      return;
+    }
+    if (name === "trusted-keys") {
+      this.handle.set_trusted_keys(newValue || "");
+    } else if (name === "kiosk") {
+      this.handle.set_kiosk(newValue !== null);
+    } else if (name === "cosigner-keys") {
+      this.handle.set_cosigner_keys(newValue || "");
+    } else if (name === "threshold") {
+      this.handle.set_threshold(threshold(newValue));
+    }
+  }
+}
+
+customElements.define("qr-verify", QrVerify);
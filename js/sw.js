@@ -0,0 +1,51 @@
+// Service worker that keeps the app available offline. The build replaces
+// PRECACHE and VERSION, see OfflinePlugin in webpack.config.js.
+const PRECACHE = __PRECACHE__;
+const CACHE = "qr-signing-" + __VERSION__;
+
+self.addEventListener("install", event => {
+  event.waitUntil(caches.open(CACHE).then(cache => cache.addAll(PRECACHE)));
+});
+
+self.addEventListener("activate", event => {
+  event.waitUntil(
+    caches.keys()
+      .then(keys => Promise.all(keys.filter(key => key !== CACHE).map(key => caches.delete(key))))
+      .then(() => self.clients.claim())
+  );
+});
+
+// The page asks for this when the user accepts the update notice.
+self.addEventListener("message", event => {
+  if (event.data && event.data.type === "skipWaiting") {
+    self.skipWaiting();
+  }
+});
+
+self.addEventListener("fetch", event => {
+  const request = event.request;
+  if (request.method !== "GET" || new URL(request.url).origin !== self.location.origin) {
+    return;
+  }
+  event.respondWith(
+    caches.open(CACHE).then(cache =>
+      cache.match(request, { ignoreSearch: request.mode === "navigate" }).then(cached => {
+        if (cached) {
+          return cached;
+        }
+        return fetch(request).then(response => {
+          // Files missing from the precache list are kept once seen.
+          if (response.ok) {
+            cache.put(request, response.clone());
+          }
+          return response;
+        }).catch(error => {
+          if (request.mode === "navigate") {
+            return cache.match("index.html");
+          }
+          throw error;
+        });
+      })
+    )
+  );
+});
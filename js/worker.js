@@ -0,0 +1 @@
+import("../pkg/index.js").then(module => module.worker_main()).catch(console.error);
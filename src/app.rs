@@ -1,16 +1,36 @@
-use crate::{crypto, qr_generator::encode_data, qr_reader::QrReader, subtle};
-use js_sys::Reflect;
-use std::io::Write;
-use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{CryptoKey, Url};
+use crate::{
+    batch::{Progress, ERRORS_FILE},
+    crypto::{self, subtle, Signer},
+    ecdsa,
+    error::Error,
+    files::{self, download, read_file, selected_file},
+    fingerprint::{self, Fingerprint},
+    key_file::{self, KeyFile},
+    metadata::Metadata,
+    payload::{self, Payload},
+    payload_url,
+    qr_generator::{encode_data, encode_data_as, Encoding},
+    qr_reader::{view_fingerprint, QrReader, Verdict},
+    quick_sign::QuickSign,
+    security_key::{self, SecurityKey},
+    verification_log::{LogEntry, VerificationLog},
+    worker::{BatchWorker, Response},
+};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{CryptoKey, SubtleCrypto};
 use yew::prelude::*;
-use zip::{write::FileOptions, ZipWriter};
+
+/// Number of results kept in the verification log.
+const LOG_SIZE: usize = 1000;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 enum AppKey {
     None,
     Public(CryptoKey),
     Pair(CryptoKey, CryptoKey),
+    /// A credential on a security key, which signs through WebAuthn.
+    SecurityKey(SecurityKey),
 }
 
 impl AppKey {
@@ -22,6 +42,7 @@ impl AppKey {
             Self::None => None,
             Self::Public(public_key) => Some(public_key.clone()),
             Self::Pair(public_key, _) => Some(public_key.clone()),
+            Self::SecurityKey(security_key) => Some(security_key.public_key.clone()),
         }
     }
 }
@@ -29,22 +50,105 @@ impl AppKey {
 pub struct Main {
     link: ComponentLink<Self>,
     key: AppKey,
+    /// Public keys replaced through rotations, most recent first.
+    previous_keys: Vec<CryptoKey>,
     qr_key: NodeRef,
     open_file: NodeRef,
     open_text: NodeRef,
-    public_hash: Option<String>,
+    open_any: NodeRef,
+    open_multi_signed: NodeRef,
+    /// Keys of other signers accepted in addition to the own key.
+    cosigners: Vec<(CryptoKey, Fingerprint)>,
+    /// Signatures of different keys required on `MULTI:` codes.
+    threshold: usize,
+    export_format: KeyFormat,
+    /// Station and batch signed along with the data.
+    metadata: Metadata,
+    /// Whether the issue time is signed along with the data.
+    timestamp: bool,
+    /// Whether signatures are derived as per RFC 6979 instead of randomized.
+    deterministic: bool,
+    /// How signed codes are stored in the QR code.
+    encoding: Encoding,
+    log: Rc<Vec<LogEntry>>,
+    /// Payload of the URL the app was opened with.
+    link_payload: Option<Vec<u8>>,
+    public_hash: Option<Fingerprint>,
+    kiosk: bool,
+    cooldown: u32,
+    batch_worker: Option<BatchWorker>,
+    batch: Option<BatchProgress>,
+}
+
+/// Format of exported key files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// Both keys as JWK in the JSON key file of the app.
+    Json,
+    /// PKCS#8 and SPKI in one PEM file.
+    Pem,
+    /// PKCS#8 and SPKI as two binary files in one ZIP archive.
+    Der,
+}
+
+impl KeyFormat {
+    const ALL: [KeyFormat; 3] = [KeyFormat::Json, KeyFormat::Pem, KeyFormat::Der];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Pem => "PEM",
+            Self::Der => "DER",
+        }
+    }
+}
+
+struct BatchProgress {
+    job: u32,
+    progress: Progress,
 }
 
 pub enum Msg {
     ImportKeyPair,
     ExportKeyPair,
+    ExportPublicKey,
+    PastePublicKey,
+    SetExportFormat(KeyFormat),
     GenerateKeyPair,
+    RegisterSecurityKey,
+    SetSecurityKey(SecurityKey),
+    SignWithSecurityKey,
+    RotateKeyPair,
+    Rotated(CryptoKey, CryptoKey),
+    SetSuccessor(CryptoKey, CryptoKey),
     KeyPairSelected,
     SetKeyPair(CryptoKey, CryptoKey),
     SetPublicKey(CryptoKey),
-    SetPublicHash([u8; 32]),
+    SetPublicHash(Fingerprint),
     Sign,
     TextFileSelected,
+    SignFile,
+    AnyFileSelected,
+    StartMultiSign,
+    CoSign(Payload),
+    CoSignFile,
+    MultiSignedFileSelected,
+    AddCosigner,
+    CosignerAdded(CryptoKey, Fingerprint),
+    RemoveCosigner(usize),
+    SetThreshold(usize),
+    StartBatch(String),
+    BatchResponse(Response),
+    CancelBatch,
+    ToggleKiosk,
+    SetCooldown(u32),
+    SetTimestamp(bool),
+    SetDeterministic(bool),
+    SetEncoding(Encoding),
+    SetStation(String),
+    SetBatch(String),
+    Logged(Verdict),
+    ClearLog,
 }
 
 impl Component for Main {
@@ -52,332 +156,463 @@ impl Component for Main {
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let link_payload = link_payload();
+        if link_payload.is_some() {
+            let link = link.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match import_configured_key().await {
+                    Ok(Some(public_key)) => link.send_message(Msg::SetPublicKey(public_key)),
+                    Ok(None) => {}
+                    Err(err) => err.report(),
+                }
+            });
+        }
         Self {
             link,
             key: AppKey::None,
+            previous_keys: Vec::new(),
             qr_key: NodeRef::default(),
             open_file: NodeRef::default(),
             open_text: NodeRef::default(),
+            open_any: NodeRef::default(),
+            open_multi_signed: NodeRef::default(),
+            cosigners: Vec::new(),
+            threshold: 1,
+            export_format: KeyFormat::Json,
+            metadata: Metadata::default(),
+            timestamp: false,
+            deterministic: false,
+            encoding: Encoding::default(),
+            log: Rc::new(Vec::new()),
+            link_payload,
             public_hash: None,
+            kiosk: false,
+            cooldown: 5,
+            batch_worker: None,
+            batch: None,
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::ImportKeyPair => {
-                let open_file = self.open_file.cast::<web_sys::HtmlInputElement>().unwrap();
-                open_file.click();
+                if let Some(open_file) = self.open_file.cast::<web_sys::HtmlInputElement>() {
+                    open_file.click();
+                }
             }
             Msg::ExportKeyPair => {
                 if let AppKey::Pair(public_key, private_key) = &self.key {
                     let public_key = public_key.clone();
                     let private_key = private_key.clone();
+                    let format = self.export_format;
                     wasm_bindgen_futures::spawn_local(async move {
-                        let subtle = subtle();
-                        let (public_key, private_key) = futures::join!(
-                            crypto::export_key(&subtle, &public_key),
-                            crypto::export_key(&subtle, &private_key)
-                        );
-                        let public_key = public_key.unwrap();
-                        let private_key = private_key.unwrap();
-                        let key_pair_json = js_sys::Object::new();
-                        Reflect::set(
-                            &key_pair_json,
-                            &JsValue::from_str("public"),
-                            public_key.unchecked_ref(),
-                        )
-                        .unwrap();
-                        Reflect::set(
-                            &key_pair_json,
-                            &JsValue::from_str("private"),
-                            private_key.unchecked_ref(),
-                        )
-                        .unwrap();
-                        Reflect::set(
-                            &key_pair_json,
-                            &JsValue::from_str("type"),
-                            &JsValue::from_str("qr_key_pair"),
-                        )
-                        .unwrap();
-
-                        let json = js_sys::JSON::stringify(key_pair_json.unchecked_ref())
-                            .unwrap()
-                            .as_string()
-                            .unwrap();
-                        let mut data_url = "data:application/json,".to_owned();
-                        data_url.push_str(&json);
-
-                        let a: web_sys::HtmlAnchorElement = web_sys::window()
-                            .unwrap()
-                            .document()
-                            .unwrap()
-                            .create_element("A")
-                            .unwrap()
-                            .unchecked_into();
-                        a.set_href(&data_url);
-                        a.set_download("qr_key.json");
-                        a.click();
+                        if let Err(err) = export_key_pair(&public_key, &private_key, format).await {
+                            err.report();
+                        }
                     });
                 }
             }
+            Msg::ExportPublicKey => {
+                if let Some(public_key) = self.key.public_key() {
+                    let format = self.export_format;
+                    wasm_bindgen_futures::spawn_local(async move {
+                        if let Err(err) = export_public_key(&public_key, format).await {
+                            err.report();
+                        }
+                    });
+                }
+            }
+            Msg::PastePublicKey => {
+                if let Some(text) = prompt("Paste the public key (PEM, JWK or base64):") {
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match import_pasted_key(&text).await {
+                            Ok(Some(public_key)) => {
+                                link.send_message(Msg::SetPublicKey(public_key))
+                            }
+                            Ok(None) => {}
+                            Err(err) => err.report(),
+                        }
+                    });
+                }
+            }
+            Msg::SetExportFormat(format) => {
+                self.export_format = format;
+            }
             Msg::GenerateKeyPair => {
                 let link = self.link.clone();
                 wasm_bindgen_futures::spawn_local(async move {
-                    let subtle = subtle();
-
-                    match crypto::generate_keypair(&subtle).await {
+                    match async { crypto::generate_keypair(&subtle()?).await }.await {
                         Ok((public_key, private_key)) => {
                             link.send_message(Msg::SetKeyPair(public_key, private_key))
                         }
-                        Err(err) => {
-                            web_sys::window()
-                                .unwrap()
-                                .alert_with_message(
-                                    &err.unchecked_into::<js_sys::Error>()
-                                        .to_string()
-                                        .as_string()
-                                        .unwrap(),
+                        Err(err) => err.report(),
+                    }
+                });
+            }
+            Msg::RegisterSecurityKey => {
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    match register_security_key().await {
+                        Ok(security_key) => link.send_message(Msg::SetSecurityKey(security_key)),
+                        Err(err) => err.report(),
+                    }
+                });
+            }
+            Msg::SetSecurityKey(security_key) => {
+                self.previous_keys.clear();
+                self.key = AppKey::SecurityKey(security_key);
+                self.update_hash_and_qr();
+            }
+            Msg::SignWithSecurityKey => {
+                if let AppKey::SecurityKey(security_key) = &self.key {
+                    if let Some(text) = prompt("Text to be signed with the security key:") {
+                        let data = self
+                            .metadata
+                            .issued_now(self.timestamp)
+                            .wrap(text.as_bytes());
+                        let security_key = security_key.clone();
+                        let encoding = self.encoding.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let result = security_key.sign(&data).await.and_then(|payload| {
+                                download(
+                                    encode_data_as(&payload.to_bytes(), &encoding)?.as_bytes(),
+                                    "signed.svg",
+                                )
+                            });
+                            if let Err(err) = result {
+                                err.report();
+                            }
+                        });
+                    }
+                }
+            }
+            Msg::RotateKeyPair => {
+                if let AppKey::Pair(public_key, private_key) = &self.key {
+                    let confirmed = web_sys::window()
+                        .and_then(|window| {
+                            window
+                                .confirm_with_message(
+                                    "Replace the key pair with a new one?\n\nVerifiers that trust the current key adopt the new one when they scan the downloaded key rotation code.",
                                 )
-                                .unwrap();
+                                .ok()
+                        })
+                        .unwrap_or(false);
+                    if !confirmed {
+                        return false;
+                    }
+                    let public_key = public_key.clone();
+                    let private_key = private_key.clone();
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match rotate_key_pair(&public_key, &private_key).await {
+                            Ok((public_key, private_key)) => {
+                                link.send_message(Msg::Rotated(public_key, private_key))
+                            }
+                            Err(err) => err.report(),
                         }
+                    });
+                }
+            }
+            Msg::Rotated(public_key, private_key) => {
+                self.retire_key();
+                self.key = AppKey::Pair(public_key, private_key);
+                self.update_hash_and_qr();
+                alert("The key pair was replaced. Export the new key pair, the previous one can't be recovered.");
+            }
+            Msg::SetSuccessor(old_key, new_key) => {
+                if !matches!(self.key, AppKey::None | AppKey::Public(_)) {
+                    let confirmed = web_sys::window()
+                        .and_then(|window| {
+                            window
+                                .confirm_with_message(
+                                    "The scanned code replaces the loaded key with a new one.\n\nSwitch to the new public key? The loaded private key is discarded, import the new key pair to keep signing.",
+                                )
+                                .ok()
+                        })
+                        .unwrap_or(false);
+                    if !confirmed {
+                        return false;
                     }
-                });
+                }
+                self.previous_keys.insert(0, old_key);
+                self.key = AppKey::Public(new_key);
+                self.update_hash_and_qr();
             }
             Msg::SetKeyPair(public_key, private_key) => {
+                self.previous_keys.clear();
                 self.key = AppKey::Pair(public_key, private_key);
                 self.update_hash_and_qr();
             }
             Msg::SetPublicKey(public_key) => {
+                self.previous_keys.clear();
                 self.key = AppKey::Public(public_key);
                 self.update_hash_and_qr();
             }
             Msg::KeyPairSelected => {
-                let element = self.open_file.cast::<web_sys::HtmlInputElement>().unwrap();
-
-                if let Some(files) = element.files() {
-                    if let Some(file) = files.get(0) {
+                if let Some(file) = selected_file(&self.open_file) {
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let key = match read_file(&file).await {
+                            Ok(data) => import_key_file(&data).await,
+                            Err(err) => Err(err),
+                        };
+                        match key {
+                            Ok(Some(msg)) => link.send_message(msg),
+                            Ok(None) => {}
+                            Err(err) => err.report(),
+                        }
+                    });
+                }
+            }
+            Msg::SetPublicHash(hash) => {
+                self.public_hash = Some(hash);
+            }
+            Msg::Sign => {
+                if let Some(open_text) = self.open_text.cast::<web_sys::HtmlInputElement>() {
+                    open_text.click();
+                }
+            }
+            Msg::TextFileSelected => {
+                if self.key.is_pair() {
+                    if let Some(file) = selected_file(&self.open_text) {
                         let link = self.link.clone();
                         wasm_bindgen_futures::spawn_local(async move {
-                            let data = wasm_bindgen_futures::JsFuture::from(file.array_buffer())
-                                .await
-                                .unwrap();
-                            let text_decoder =
-                                web_sys::TextDecoder::new_with_label("utf-8").unwrap();
-                            match text_decoder.decode_with_buffer_source(data.unchecked_ref()) {
-                                Err(err) => {
-                                    web_sys::window()
-                                        .unwrap()
-                                        .alert_with_message(
-                                            &err.unchecked_into::<js_sys::Error>()
-                                                .to_string()
-                                                .as_string()
-                                                .unwrap(),
-                                        )
-                                        .unwrap();
-                                }
-                                Ok(text) => match js_sys::JSON::parse(&text) {
-                                    Err(err) => {
-                                        web_sys::window()
-                                            .unwrap()
-                                            .alert_with_message(
-                                                &err.unchecked_into::<js_sys::Error>()
-                                                    .to_string()
-                                                    .as_string()
-                                                    .unwrap(),
-                                            )
-                                            .unwrap();
-                                    }
-                                    Ok(json) => {
-                                        if Some("qr_key_pair".to_owned())
-                                            == Reflect::get(&json, &JsValue::from_str("type"))
-                                                .unwrap()
-                                                .as_string()
-                                        {
-                                            let public =
-                                                Reflect::get(&json, &JsValue::from_str("public"))
-                                                    .unwrap();
-                                            let private =
-                                                Reflect::get(&json, &JsValue::from_str("private"))
-                                                    .unwrap();
-                                            if public.is_falsy() || private.is_falsy() {
-                                                web_sys::window()
-                                                    .unwrap()
-                                                    .alert_with_message(
-                                                        "This file is not a key pair!",
-                                                    )
-                                                    .unwrap();
-                                            }
-                                            let subtle = subtle();
-                                            match crypto::import_public_key(
-                                                &subtle,
-                                                public.unchecked_ref(),
-                                            )
-                                            .await
-                                            {
-                                                Err(err) => {
-                                                    web_sys::window()
-                                                        .unwrap()
-                                                        .alert_with_message(
-                                                            &err.unchecked_into::<js_sys::Error>()
-                                                                .to_string()
-                                                                .as_string()
-                                                                .unwrap(),
-                                                        )
-                                                        .unwrap();
-                                                }
-                                                Ok(public_key) => match crypto::import_private_key(
-                                                    &subtle,
-                                                    private.unchecked_ref(),
-                                                )
-                                                .await
-                                                {
-                                                    Err(err) => {
-                                                        web_sys::window()
-                                                            .unwrap()
-                                                            .alert_with_message(
-                                                                &err.unchecked_into::<js_sys::Error>()
-                                                                    .to_string()
-                                                                    .as_string()
-                                                                    .unwrap(),
-                                                            )
-                                                            .unwrap();
-                                                    }
-                                                    Ok(private_key) => {
-                                                        link.send_message(Msg::SetKeyPair(
-                                                            public_key,
-                                                            private_key,
-                                                        ));
-                                                    }
-                                                },
-                                            }
-                                        } else {
-                                            web_sys::window()
-                                                .unwrap()
-                                                .alert_with_message("This file is not a key pair!")
-                                                .unwrap();
-                                        }
-                                    }
-                                },
+                            let text = read_file(&file).await.and_then(|data| {
+                                String::from_utf8(data).map_err(|_| {
+                                    Error::FileDecoding(
+                                        "The provided file is not UTF-8 encoded text.".to_owned(),
+                                    )
+                                })
+                            });
+                            match text {
+                                Ok(text) => link.send_message(Msg::StartBatch(text)),
+                                Err(err) => err.report(),
                             }
                         });
                     }
                 }
             }
-            Msg::SetPublicHash(hash) => {
-                let text = hash
+            Msg::SignFile => {
+                if let Some(open_any) = self.open_any.cast::<web_sys::HtmlInputElement>() {
+                    open_any.click();
+                }
+            }
+            Msg::AnyFileSelected => {
+                if let AppKey::Pair(_, private_key) = &self.key {
+                    if let Some(file) = selected_file(&self.open_any) {
+                        let private_key = private_key.clone();
+                        let deterministic = self.deterministic;
+                        let encoding = self.encoding.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            if let Err(err) =
+                                sign_file(&private_key, deterministic, encoding, &file).await
+                            {
+                                err.report();
+                            }
+                        });
+                    }
+                }
+            }
+            Msg::StartMultiSign => {
+                if let AppKey::Pair(public_key, private_key) = &self.key {
+                    if let Some(text) = prompt("Text to be signed by several keys:") {
+                        let metadata = self.metadata.issued_now(self.timestamp);
+                        let public_key = public_key.clone();
+                        let private_key = private_key.clone();
+                        let deterministic = self.deterministic;
+                        let encoding = self.encoding.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let result = async {
+                                let subtle = subtle()?;
+                                let signer =
+                                    Signer::new(&subtle, &private_key, deterministic).await?;
+                                let mut payload = Payload::MultiSigned {
+                                    signatures: Vec::new(),
+                                    data: metadata.wrap(text.as_bytes()),
+                                };
+                                crypto::cosign(&subtle, &public_key, &signer, &mut payload).await?;
+                                download_multi_signed(&payload, &encoding)
+                            }
+                            .await;
+                            if let Err(err) = result {
+                                err.report();
+                            }
+                        });
+                    }
+                }
+            }
+            Msg::CoSign(payload) => {
+                if let AppKey::Pair(public_key, private_key) = &self.key {
+                    let public_key = public_key.clone();
+                    let private_key = private_key.clone();
+                    let deterministic = self.deterministic;
+                    let encoding = self.encoding.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result =
+                            cosign(&public_key, &private_key, deterministic, encoding, payload);
+                        if let Err(err) = result.await {
+                            err.report();
+                        }
+                    });
+                }
+            }
+            Msg::CoSignFile => {
+                if let Some(open_multi_signed) =
+                    self.open_multi_signed.cast::<web_sys::HtmlInputElement>()
+                {
+                    open_multi_signed.click();
+                }
+            }
+            Msg::MultiSignedFileSelected => {
+                if let Some(file) = selected_file(&self.open_multi_signed) {
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match read_file(&file)
+                            .await
+                            .and_then(|data| Payload::parse(&data))
+                        {
+                            Ok(payload) => link.send_message(Msg::CoSign(payload)),
+                            Err(err) => err.report(),
+                        }
+                    });
+                }
+            }
+            Msg::AddCosigner => {
+                if let Some(text) =
+                    prompt("Paste the public key of the co-signer (PEM, JWK or base64):")
+                {
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match import_cosigner(&text).await {
+                            Ok(Some(msg)) => link.send_message(msg),
+                            Ok(None) => {}
+                            Err(err) => err.report(),
+                        }
+                    });
+                }
+            }
+            Msg::CosignerAdded(public_key, fingerprint) => {
+                if self
+                    .cosigners
                     .iter()
-                    .map(|byte| format!("{:02X}", byte))
-                    .collect::<Vec<String>>()
-                    .join(":");
-                self.public_hash = Some(text);
+                    .any(|(_, known)| *known == fingerprint)
+                {
+                    return false;
+                }
+                self.cosigners.push((public_key, fingerprint));
             }
-            Msg::Sign => {
-                let open_text = self.open_text.cast::<web_sys::HtmlInputElement>().unwrap();
-                open_text.click();
+            Msg::RemoveCosigner(index) => {
+                if index < self.cosigners.len() {
+                    self.cosigners.remove(index);
+                }
             }
-            Msg::TextFileSelected => {
-                let element = self.open_text.cast::<web_sys::HtmlInputElement>().unwrap();
-
+            Msg::SetThreshold(threshold) => {
+                self.threshold = threshold.max(1);
+            }
+            Msg::StartBatch(text) => {
                 if let AppKey::Pair(_, private_key) = &self.key {
-                    if let Some(files) = element.files() {
-                        if let Some(file) = files.get(0) {
-                            let private_key = private_key.clone();
-                            wasm_bindgen_futures::spawn_local(async move {
-                                let data =
-                                    wasm_bindgen_futures::JsFuture::from(file.array_buffer())
-                                        .await
-                                        .unwrap();
-                                let data = js_sys::Uint8Array::new(&data).to_vec();
-                                match String::from_utf8(data) {
-                                    Err(_) => {
-                                        web_sys::window()
-                                            .unwrap()
-                                            .alert_with_message(
-                                                "The provided file is not UTF-8 encoded text.",
-                                            )
-                                            .unwrap();
-                                    }
-                                    Ok(text) => {
-                                        let subtle = subtle();
-                                        let lines: Vec<_> = text.lines().collect();
-                                        match futures::future::join_all(
-                                            lines.iter().map(|line| {
-                                                crypto::sign(&subtle, &private_key, line)
-                                            }),
-                                        )
-                                        .await
-                                        .into_iter()
-                                        .collect::<Result<Vec<_>, _>>()
-                                        {
-                                            Err(err) => {
-                                                web_sys::window()
-                                                    .unwrap()
-                                                    .alert_with_message(
-                                                        &err.unchecked_into::<js_sys::Error>()
-                                                            .to_string()
-                                                            .as_string()
-                                                            .unwrap(),
-                                                    )
-                                                    .unwrap();
-                                            }
-                                            Ok(signed) => {
-                                                let mut data: Vec<u8> = Vec::new();
-                                                {
-                                                    let mut cursor =
-                                                        std::io::Cursor::new(&mut data);
-                                                    let mut zip = ZipWriter::new(&mut cursor);
-                                                    for (idx, entry) in
-                                                        signed.into_iter().enumerate()
-                                                    {
-                                                        let bytes = js_sys::Uint8Array::new(&entry)
-                                                            .to_vec();
-                                                        let mut signed_data = b"SIGN:".to_vec();
-                                                        signed_data.extend_from_slice(&bytes);
-                                                        signed_data.extend_from_slice(
-                                                            lines[idx].as_bytes(),
-                                                        );
-
-                                                        let svg =
-                                                            encode_data(&signed_data).unwrap();
-                                                        zip.start_file(
-                                                            format!("signed_{}.svg", idx + 1),
-                                                            FileOptions::default(),
-                                                        )
-                                                        .unwrap();
-                                                        zip.write_all(svg.as_bytes()).unwrap();
-                                                    }
-                                                    zip.finish().unwrap();
-                                                }
-                                                let buffer =
-                                                    js_sys::Uint8Array::from(data.as_slice());
-                                                let blob = web_sys::Blob::new_with_blob_sequence(
-                                                    &js_sys::Array::of1(&buffer),
-                                                )
-                                                .unwrap();
-                                                let blob_url =
-                                                    Url::create_object_url_with_blob(&blob)
-                                                        .unwrap();
-                                                let a: web_sys::HtmlAnchorElement =
-                                                    web_sys::window()
-                                                        .unwrap()
-                                                        .document()
-                                                        .unwrap()
-                                                        .create_element("A")
-                                                        .unwrap()
-                                                        .unchecked_into();
-                                                a.set_href(&blob_url);
-                                                a.set_download("signed.zip");
-                                                a.click();
-                                                Url::revoke_object_url(&blob_url).unwrap();
-                                            }
-                                        }
-                                    }
-                                }
-                            });
+                    if self.batch_worker.is_none() {
+                        match BatchWorker::new(self.link.callback(Msg::BatchResponse)) {
+                            Ok(worker) => self.batch_worker = Some(worker),
+                            Err(err) => {
+                                err.report();
+                                return false;
+                            }
+                        }
+                    }
+                    let metadata = self.metadata.issued_now(self.timestamp);
+                    if let Some(worker) = self.batch_worker.as_mut() {
+                        let total = text.lines().count();
+                        match worker.sign_batch(
+                            private_key,
+                            text,
+                            metadata,
+                            self.deterministic,
+                            self.encoding.clone(),
+                        ) {
+                            Ok(job) => {
+                                self.batch = Some(BatchProgress {
+                                    job,
+                                    progress: Progress {
+                                        total,
+                                        ..Progress::default()
+                                    },
+                                })
+                            }
+                            Err(err) => err.report(),
                         }
                     }
                 }
             }
+            Msg::BatchResponse(response) => {
+                let current = self.batch.as_ref().map(|batch| batch.job);
+                match response {
+                    Response::Progress { job, progress } if Some(job) == current => {
+                        self.batch = Some(BatchProgress { job, progress });
+                    }
+                    Response::Finished { job, zip, failed } if Some(job) == current => {
+                        self.batch = None;
+                        if let Err(err) = download(&zip, "signed.zip") {
+                            err.report();
+                        } else if failed > 0 {
+                            alert(&format!(
+                                "{} line(s) could not be signed, see {} in signed.zip.",
+                                failed, ERRORS_FILE
+                            ));
+                        }
+                    }
+                    Response::Cancelled { job } if Some(job) == current => {
+                        self.batch = None;
+                    }
+                    Response::Failed { job, message } if Some(job) == current => {
+                        self.batch = None;
+                        alert(&message);
+                    }
+                    _ => return false,
+                }
+            }
+            Msg::CancelBatch => {
+                if let (Some(worker), Some(batch)) = (&self.batch_worker, self.batch.take()) {
+                    if let Err(err) = worker.cancel(batch.job) {
+                        err.report();
+                    }
+                }
+            }
+            Msg::ToggleKiosk => {
+                self.kiosk = !self.kiosk;
+            }
+            Msg::SetCooldown(cooldown) => {
+                self.cooldown = cooldown;
+            }
+            Msg::SetTimestamp(timestamp) => {
+                self.timestamp = timestamp;
+            }
+            Msg::SetDeterministic(deterministic) => {
+                self.deterministic = deterministic;
+            }
+            Msg::SetEncoding(encoding) => {
+                self.encoding = encoding;
+            }
+            Msg::SetStation(station) => {
+                self.metadata.station = Some(station).filter(|station| !station.is_empty());
+            }
+            Msg::SetBatch(batch) => {
+                self.metadata.batch = Some(batch).filter(|batch| !batch.is_empty());
+            }
+            Msg::Logged(verdict) => {
+                let log = Rc::make_mut(&mut self.log);
+                if log.len() >= LOG_SIZE {
+                    log.remove(0);
+                }
+                log.push(LogEntry {
+                    scanned_at: js_sys::Date::now(),
+                    verdict,
+                });
+            }
+            Msg::ClearLog => {
+                self.log = Rc::new(Vec::new());
+            }
         }
         true
     }
@@ -391,50 +626,518 @@ impl Component for Main {
             <div>
                 <header>
                     <button onclick=self.link.callback(|_| Msg::GenerateKeyPair) class="mdi-set mdi-briefcase-outline" title="Generate Key Pair"></button>
-                    <button onclick=self.link.callback(|_| Msg::ImportKeyPair) class="mdi-set mdi-briefcase-upload" title="Import Key Pair"></button>
+                    <button onclick=self.link.callback(|_| Msg::ImportKeyPair) class="mdi-set mdi-briefcase-upload" title="Import Key Pair or Public Key"></button>
+                    <button onclick=self.link.callback(|_| Msg::PastePublicKey) class="mdi-set mdi-key-plus" title="Paste Public Key"></button>
                     <button onclick=self.link.callback(|_| Msg::ExportKeyPair) class="mdi-set mdi-briefcase-download" title="Export Key Pair" disabled={ !self.key.is_pair() }></button>
-                    <button onclick=self.link.callback(|_| Msg::Sign) class="mdi-set mdi-feather" title="Batch sign text" disabled={ !self.key.is_pair() }></button>
+                    <button onclick=self.link.callback(|_| Msg::RegisterSecurityKey) class="mdi-set mdi-usb-flash-drive-outline" title="Register Security Key" disabled={ !security_key::is_supported() }></button>
+                    <button onclick=self.link.callback(|_| Msg::RotateKeyPair) class="mdi-set mdi-key-change" title="Rotate Key Pair" disabled={ !self.key.is_pair() }></button>
+                    <button onclick=self.link.callback(|_| Msg::ExportPublicKey) class="mdi-set mdi-key-arrow-right" title="Export Public Key" disabled={ self.key.public_key().is_none() }></button>
+                    { self.view_export_format() }
+                    {
+                        if self.batch.is_some() {
+                            html! { <button onclick=self.link.callback(|_| Msg::CancelBatch) class="mdi-set mdi-cancel" title="Cancel batch signing"></button> }
+                        } else {
+                            html! { <button onclick=self.link.callback(|_| Msg::Sign) class="mdi-set mdi-feather" title="Batch sign text" disabled={ !self.key.is_pair() }></button> }
+                        }
+                    }
+                    <button onclick=self.link.callback(|_| Msg::SignFile) class="mdi-set mdi-file-certificate-outline" title="Sign file" disabled={ !self.key.is_pair() }></button>
+                    <button onclick=self.link.callback(|_| Msg::StartMultiSign) class="mdi-set mdi-account-multiple-plus" title="Start multi-signature code" disabled={ !self.key.is_pair() }></button>
+                    <button onclick=self.link.callback(|_| Msg::SignWithSecurityKey) class="mdi-set mdi-shield-key-outline" title="Sign text with security key" disabled={ !matches!(self.key, AppKey::SecurityKey(_)) }></button>
+                    <button onclick=self.link.callback(|_| Msg::CoSignFile) class="mdi-set mdi-file-sign" title="Co-sign multi-signature file" disabled={ !self.key.is_pair() }></button>
+                    <button onclick=self.link.callback(|_| Msg::ToggleKiosk) class=("mdi-set", "mdi-monitor-lock", if self.kiosk { "active" } else { "" }) title="Kiosk Mode"></button>
                     <div class="key_qr" ref=self.qr_key.clone()></div>
                 </header>
                 <div class="hash">
                     { "Public Key Fingerprint:" }
                     <br />
                     {
-                        self.public_hash.as_deref().unwrap_or("<no public key loaded>")
+                        match &self.public_hash {
+                            Some(hash) => view_fingerprint(hash),
+                            None => html! { "<no public key loaded>" },
+                        }
                     }
                 </div>
-                <QrReader onpublickey=self.link.callback(|msg: CryptoKey| Msg::SetPublicKey(msg)) public_key={ self.key.public_key() }/>
-                <input class="hidden" type="file" accept="application/json" ref=self.open_file.clone() onchange=self.link.callback(|_| Msg::KeyPairSelected) multiple=false />
+                { self.view_quick_sign() }
+                { self.view_batch_progress() }
+                { self.view_metadata_settings() }
+                { self.view_kiosk_settings() }
+                { self.view_policy() }
+                <QrReader
+                    onpublickey=self.link.callback(|msg: CryptoKey| Msg::SetPublicKey(msg))
+                    public_key={ self.key.public_key() }
+                    previous_keys=self.previous_keys.clone()
+                    onsuccessor=self.link.callback(|(old_key, new_key)| Msg::SetSuccessor(old_key, new_key))
+                    cosigner_keys=self.cosigners.iter().map(|(public_key, _)| public_key.clone()).collect::<Vec<_>>()
+                    threshold=self.threshold
+                    onresult=self.link.callback(Msg::Logged)
+                    oncosign=if self.key.is_pair() { Some(self.link.callback(Msg::CoSign)) } else { None }
+                    link_payload=self.link_payload.clone()
+                    kiosk=self.kiosk
+                    cooldown=self.cooldown/>
+                { self.view_log() }
+                <input class="hidden" type="file" accept=".json,.pem,.der,.key,.pub,application/json" ref=self.open_file.clone() onchange=self.link.callback(|_| Msg::KeyPairSelected) multiple=false />
                 <input class="hidden" type="file" accept="text/plain" ref=self.open_text.clone() onchange=self.link.callback(|_| Msg::TextFileSelected) multiple=false />
+                <input class="hidden" type="file" ref=self.open_any.clone() onchange=self.link.callback(|_| Msg::AnyFileSelected) multiple=false />
+                <input class="hidden" type="file" accept=".multisig" ref=self.open_multi_signed.clone() onchange=self.link.callback(|_| Msg::MultiSignedFileSelected) multiple=false />
             </div>
         }
     }
 }
 
 impl Main {
+    fn view_export_format(&self) -> Html {
+        html! {
+            <select class="export_format" title="Export Format" onchange=self.link.batch_callback(|data: ChangeData| match data {
+                ChangeData::Select(select) => KeyFormat::ALL
+                    .iter()
+                    .find(|format| format.name() == select.value())
+                    .map(|format| vec![Msg::SetExportFormat(*format)])
+                    .unwrap_or_default(),
+                _ => vec![],
+            })>
+                { for KeyFormat::ALL.iter().map(|format| html! {
+                    <option value=format.name() selected={ *format == self.export_format }>{ format.name() }</option>
+                }) }
+            </select>
+        }
+    }
+
+    fn view_quick_sign(&self) -> Html {
+        match &self.key {
+            AppKey::Pair(_, private_key) => html! {
+                <QuickSign private_key=private_key.clone() metadata=self.metadata.clone() timestamp=self.timestamp deterministic=self.deterministic encoding=self.encoding.clone() />
+            },
+            _ => html! {},
+        }
+    }
+
+    fn view_batch_progress(&self) -> Html {
+        match &self.batch {
+            None => html! {},
+            Some(BatchProgress { progress, .. }) => html! {
+                <div class="batch_progress">
+                    <label>
+                        { format!("Signed {} of {} lines", progress.signed, progress.total) }
+                        <progress max={ progress.total.to_string() } value={ progress.signed.to_string() }></progress>
+                    </label>
+                    <label>
+                        { format!("Rendered {} of {} codes", progress.rendered, progress.total) }
+                        <progress max={ progress.total.to_string() } value={ progress.rendered.to_string() }></progress>
+                    </label>
+                    <button onclick=self.link.callback(|_| Msg::CancelBatch)>{ "Cancel" }</button>
+                </div>
+            },
+        }
+    }
+
+    fn view_kiosk_settings(&self) -> Html {
+        if !self.kiosk {
+            return html! {};
+        }
+        html! {
+            <div class="kiosk_settings">
+                <label>
+                    { "Ignore repeated codes for (seconds): " }
+                    <input type="number" min="0" value={ self.cooldown.to_string() } oninput=self.link.callback(|data: InputData| Msg::SetCooldown(data.value.parse().unwrap_or(0))) />
+                </label>
+            </div>
+        }
+    }
+
+    fn view_metadata_settings(&self) -> Html {
+        if !matches!(self.key, AppKey::Pair(_, _) | AppKey::SecurityKey(_)) {
+            return html! {};
+        }
+        // Security keys sign the same metadata, but pick their own nonces.
+        let deterministic = if self.key.is_pair() {
+            html! {
+                <label title="The same text and key always give the same code, unless the issue time is signed.">
+                    <input type="checkbox" checked=self.deterministic onclick=self.link.callback({
+                        let deterministic = self.deterministic;
+                        move |_| Msg::SetDeterministic(!deterministic)
+                    }) />
+                    { "Reproducible signatures (RFC 6979)" }
+                </label>
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <div class="metadata_settings">
+                <label>
+                    <input type="checkbox" checked=self.timestamp onclick=self.link.callback({
+                        let timestamp = self.timestamp;
+                        move |_| Msg::SetTimestamp(!timestamp)
+                    }) />
+                    { "Sign issue time" }
+                </label>
+                { deterministic }
+                <label title="Base45 text in alphanumeric mode is read reliably by more scanners.">
+                    { "QR mode: " }
+                    <select onchange=self.link.batch_callback(|data: ChangeData| match data {
+                        ChangeData::Select(select) => Encoding::from_name(&select.value(), &verify_url())
+                            .map(|encoding| vec![Msg::SetEncoding(encoding)])
+                            .unwrap_or_default(),
+                        _ => vec![],
+                    })>
+                        { for Encoding::all(&verify_url()).iter().map(|encoding| html! {
+                            <option value=encoding.name() selected={ *encoding == self.encoding }>{ encoding.name() }</option>
+                        }) }
+                    </select>
+                </label>
+                <input type="text" placeholder="Station" value=self.metadata.station.clone().unwrap_or_default() oninput=self.link.callback(|data: InputData| Msg::SetStation(data.value)) />
+                <input type="text" placeholder="Batch" value=self.metadata.batch.clone().unwrap_or_default() oninput=self.link.callback(|data: InputData| Msg::SetBatch(data.value)) />
+            </div>
+        }
+    }
+
+    fn view_log(&self) -> Html {
+        if self.log.is_empty() {
+            return html! {};
+        }
+        html! {
+            <VerificationLog entries=self.log.clone() onclear=self.link.callback(|_| Msg::ClearLog) />
+        }
+    }
+
+    fn view_policy(&self) -> Html {
+        html! {
+            <div class="policy">
+                <label>
+                    { "Signatures required on multi-signature codes: " }
+                    <input type="number" min="1" value={ self.threshold.to_string() } oninput=self.link.callback(|data: InputData| Msg::SetThreshold(data.value.parse().unwrap_or(1))) />
+                </label>
+                <ul class="cosigners">
+                    { for self.cosigners.iter().enumerate().map(|(index, (_, fingerprint))| html! {
+                        <li>
+                            { view_fingerprint(fingerprint) }
+                            <button onclick=self.link.callback(move |_| Msg::RemoveCosigner(index)) class="mdi-set mdi-close" title="Remove Co-Signer"></button>
+                        </li>
+                    }) }
+                </ul>
+                <button onclick=self.link.callback(|_| Msg::AddCosigner) class="mdi-set mdi-account-key" title="Add Co-Signer Key"></button>
+            </div>
+        }
+    }
+
+    /// Keeps the current public key for verifying codes signed before a rotation.
+    fn retire_key(&mut self) {
+        if let Some(public_key) = self.key.public_key() {
+            self.previous_keys.insert(0, public_key);
+        }
+    }
+
     fn update_hash_and_qr(&self) {
-        let public_key = match &self.key {
-            AppKey::Pair(public_key, _) => public_key.clone(),
-            AppKey::Public(public_key) => public_key.clone(),
-            AppKey::None => return,
+        let public_key = match self.key.public_key() {
+            Some(public_key) => public_key,
+            None => return,
+        };
+        let qr_div = match self.qr_key.cast::<web_sys::Element>() {
+            Some(qr_div) => qr_div,
+            None => return,
         };
-        let qr_div = self.qr_key.cast::<web_sys::Element>().unwrap();
         let link = self.link.clone();
 
         wasm_bindgen_futures::spawn_local(async move {
-            let public_key = crypto::export_key_raw(&subtle(), &public_key)
-                .await
-                .unwrap();
-            let public_key_u8 = js_sys::Uint8Array::new(public_key.unchecked_ref());
-            let public_key = public_key_u8.to_vec();
-            let mut data = Vec::new();
-            data.extend_from_slice(b"PUB:");
-            data.extend_from_slice(&public_key);
-
-            link.send_message(Msg::SetPublicHash(hmac_sha256::Hash::hash(&public_key)));
-
-            let qr_svg = encode_data(&data).unwrap();
-            qr_div.set_inner_html(&qr_svg);
+            let result = async {
+                let public_key = crypto::export_key_raw(&subtle()?, &public_key).await?;
+                let public_key = js_sys::Uint8Array::new(public_key.unchecked_ref()).to_vec();
+
+                link.send_message(Msg::SetPublicHash(fingerprint::fingerprint(&public_key)));
+
+                let public_key = ecdsa::compress_public_key(&public_key)?;
+                encode_data(&Payload::PublicKey(public_key).to_bytes())
+            }
+            .await;
+            match result {
+                Ok(qr_svg) => qr_div.set_inner_html(&qr_svg),
+                Err(err) => err.report(),
+            }
         });
     }
 }
+
+fn alert(message: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.alert_with_message(message);
+    }
+}
+
+/// Name of the `<meta>` tag with the verifier URL for codes in URL form.
+/// Defaults to the URL of the app itself.
+const VERIFY_URL_META: &str = "qr-signing-verify-url";
+/// Name of the `<meta>` tag with the public key (base64, PEM or JWK) that codes
+/// opened as URL are verified with.
+const PUBLIC_KEY_META: &str = "qr-signing-public-key";
+
+/// Returns the content of the `<meta>` tag with the given name.
+fn meta_content(name: &str) -> Option<String> {
+    web_sys::window()?
+        .document()?
+        .query_selector(&format!("meta[name=\"{}\"]", name))
+        .ok()??
+        .get_attribute("content")
+        .filter(|content| !content.trim().is_empty())
+}
+
+/// The verifier URL of this deployment, which URL-form codes link to.
+fn verify_url() -> String {
+    meta_content(VERIFY_URL_META)
+        .or_else(|| web_sys::window()?.location().href().ok())
+        .unwrap_or_default()
+}
+
+/// Decodes the payload of the URL the app was opened with.
+fn link_payload() -> Option<Vec<u8>> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    if !payload_url::is_payload_fragment(&hash) {
+        return None;
+    }
+    match payload_url::decode_fragment(&hash) {
+        Ok(payload) => Some(payload),
+        Err(err) => {
+            err.report();
+            None
+        }
+    }
+}
+
+/// Imports the public key configured for this deployment, if any.
+async fn import_configured_key() -> Result<Option<CryptoKey>, Error> {
+    let text = match meta_content(PUBLIC_KEY_META) {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+    let (public_key, _) =
+        crypto::import_key_file(&subtle()?, &KeyFile::parse(text.as_bytes())?).await?;
+    Ok(Some(public_key))
+}
+
+/// Asks the user for text, returning `None` if cancelled or left empty.
+fn prompt(message: &str) -> Option<String> {
+    web_sys::window()
+        .and_then(|window| window.prompt_with_message(message).ok())
+        .flatten()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Adds the signature of the key pair to a multi-signature code after asking
+/// the user, and downloads the result.
+async fn cosign(
+    public_key: &CryptoKey,
+    private_key: &CryptoKey,
+    deterministic: bool,
+    encoding: Encoding,
+    mut payload: Payload,
+) -> Result<(), Error> {
+    let (count, text) = match &payload {
+        Payload::MultiSigned { signatures, data } => {
+            (signatures.len(), String::from_utf8_lossy(data).into_owned())
+        }
+        _ => return Err(Error::malformed("This code can't be co-signed.")),
+    };
+    let confirmed = web_sys::window()
+        .and_then(|window| {
+            window
+                .confirm_with_message(&format!(
+                    "Add your signature to this code with {} signature(s)?\n\n{}",
+                    count, text
+                ))
+                .ok()
+        })
+        .unwrap_or(false);
+    if !confirmed {
+        return Ok(());
+    }
+    let subtle = subtle()?;
+    let signer = Signer::new(&subtle, private_key, deterministic).await?;
+    crypto::cosign(&subtle, public_key, &signer, &mut payload).await?;
+    download_multi_signed(&payload, &encoding)
+}
+
+/// Downloads a multi-signature code as QR code and as file for passing it on to
+/// the next signer, both in one archive.
+fn download_multi_signed(payload: &Payload, encoding: &Encoding) -> Result<(), Error> {
+    let bytes = payload.to_bytes();
+    let svg = encode_data_as(&bytes, encoding)?;
+    let archive = files::zip(&[
+        ("multi_signed.svg", svg.as_bytes()),
+        ("multi_signed.multisig", &bytes),
+    ])?;
+    download(&archive, "multi_signed.zip")
+}
+
+/// Signs the hash and name of a file and downloads the QR code as `<name>.svg`.
+async fn sign_file(
+    private_key: &CryptoKey,
+    deterministic: bool,
+    encoding: Encoding,
+    file: &web_sys::File,
+) -> Result<(), Error> {
+    let contents = read_file(file).await?;
+    let name = file.name();
+    let hash = payload::file_hash(&contents);
+    let subtle = subtle()?;
+    let signer = Signer::new(&subtle, private_key, deterministic).await?;
+    let signature = signer
+        .sign(&subtle, &payload::file_message(&hash, &name))
+        .await?;
+    let qr_svg = encode_data_as(
+        &Payload::File {
+            signature,
+            hash,
+            name: name.clone(),
+        }
+        .to_bytes(),
+        &encoding,
+    )?;
+    download(qr_svg.as_bytes(), &format!("{}.svg", name))
+}
+
+/// Generates the successor of a key pair and downloads the key rotation code
+/// signed by both, returning the new key pair.
+async fn rotate_key_pair(
+    public_key: &CryptoKey,
+    private_key: &CryptoKey,
+) -> Result<(CryptoKey, CryptoKey), Error> {
+    let subtle = subtle()?;
+    let (new_public_key, new_private_key) = crypto::generate_keypair(&subtle).await?;
+    let rotation = crypto::sign_rotation(
+        &subtle,
+        public_key,
+        private_key,
+        &new_public_key,
+        &new_private_key,
+    )
+    .await?;
+    download(
+        encode_data(&rotation.to_bytes())?.as_bytes(),
+        "qr_key_rotation.svg",
+    )?;
+    Ok((new_public_key, new_private_key))
+}
+
+/// Imports a key file in any supported format, returning the message that sets
+/// the key pair or only the public key.
+///
+/// Files with only a public key are checked like pasted keys, see
+/// [`confirm_public_key`]. Returns `None` if the user rejected the key.
+async fn import_key_file(data: &[u8]) -> Result<Option<Msg>, Error> {
+    let subtle = subtle()?;
+    if let Some(security_key) = SecurityKey::from_file(&subtle, data).await? {
+        return Ok(Some(Msg::SetSecurityKey(security_key)));
+    }
+    match crypto::import_key_file(&subtle, &KeyFile::parse(data)?).await? {
+        (public_key, Some(private_key)) => Ok(Some(Msg::SetKeyPair(public_key, private_key))),
+        (public_key, None) => Ok(confirm_public_key(&subtle, &public_key)
+            .await?
+            .then(|| Msg::SetPublicKey(public_key))),
+    }
+}
+
+/// Registers a new credential on a security key and downloads the file needed
+/// to use it again after reloading the app.
+async fn register_security_key() -> Result<SecurityKey, Error> {
+    let subtle = subtle()?;
+    let security_key = SecurityKey::register(&subtle, "QR code signing").await?;
+    download(
+        security_key.to_file(&subtle).await?.as_bytes(),
+        "qr_security_key.json",
+    )?;
+    Ok(security_key)
+}
+
+/// Imports a public key pasted as text, after the user compared its fingerprint.
+///
+/// Returns `None` if the user rejected the key.
+async fn import_pasted_key(text: &str) -> Result<Option<CryptoKey>, Error> {
+    let subtle = subtle()?;
+    let (public_key, _) =
+        crypto::import_key_file(&subtle, &KeyFile::parse(text.as_bytes())?).await?;
+    Ok(confirm_public_key(&subtle, &public_key)
+        .await?
+        .then_some(public_key))
+}
+
+/// Asks the user to compare the fingerprint of a public key with the one they
+/// got from the signer.
+async fn confirm_public_key(subtle: &SubtleCrypto, public_key: &CryptoKey) -> Result<bool, Error> {
+    let fingerprint = crypto::fingerprint_of(subtle, public_key).await?;
+    let confirmed = web_sys::window()
+        .and_then(|window| {
+            window
+                .confirm_with_message(&format!(
+                    "Import public key with fingerprint\n\n{}\n\nOnly continue if it matches the fingerprint you got from the signer.",
+                    fingerprint::short_with_names(&fingerprint)
+                ))
+                .ok()
+        })
+        .unwrap_or(false);
+    Ok(confirmed)
+}
+
+/// Imports a pasted public key of another signer, see [`import_pasted_key`].
+async fn import_cosigner(text: &str) -> Result<Option<Msg>, Error> {
+    match import_pasted_key(text).await? {
+        Some(public_key) => {
+            let fingerprint = crypto::fingerprint_of(&subtle()?, &public_key).await?;
+            Ok(Some(Msg::CosignerAdded(public_key, fingerprint)))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn export_public_key(public_key: &CryptoKey, format: KeyFormat) -> Result<(), Error> {
+    let subtle = subtle()?;
+    match format {
+        KeyFormat::Json => {
+            let jwk = crypto::export_key(&subtle, public_key).await?;
+            let json = js_sys::JSON::stringify(&jwk)?
+                .as_string()
+                .unwrap_or_default();
+            download(json.as_bytes(), "qr_public_key.json")
+        }
+        KeyFormat::Pem => {
+            let public_key = crypto::export_key_spki(&subtle, public_key).await?;
+            let pem = key_file::pem_encode(key_file::PUBLIC_KEY_LABEL, &public_key);
+            download(pem.as_bytes(), "qr_public_key.pem")
+        }
+        KeyFormat::Der => {
+            let public_key = crypto::export_key_spki(&subtle, public_key).await?;
+            download(&public_key, "qr_public_key.der")
+        }
+    }
+}
+
+async fn export_key_pair(
+    public_key: &CryptoKey,
+    private_key: &CryptoKey,
+    format: KeyFormat,
+) -> Result<(), Error> {
+    let subtle = subtle()?;
+    match format {
+        KeyFormat::Json => {
+            let json = crypto::export_key_pair_file(&subtle, public_key, private_key).await?;
+            download(json.as_bytes(), "qr_key.json")
+        }
+        KeyFormat::Pem => {
+            let private_key = crypto::export_key_pkcs8(&subtle, private_key).await?;
+            let public_key = crypto::export_key_spki(&subtle, public_key).await?;
+            let pem = [
+                key_file::pem_encode(key_file::PRIVATE_KEY_LABEL, &private_key),
+                key_file::pem_encode(key_file::PUBLIC_KEY_LABEL, &public_key),
+            ]
+            .concat();
+            download(pem.as_bytes(), "qr_key.pem")
+        }
+        KeyFormat::Der => {
+            let private_key = crypto::export_key_pkcs8(&subtle, private_key).await?;
+            let public_key = crypto::export_key_spki(&subtle, public_key).await?;
+            let archive = files::zip(&[
+                ("qr_key.der", &private_key),
+                ("qr_key.pub.der", &public_key),
+            ])?;
+            download(&archive, "qr_key.zip")
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Base45 as specified in RFC 9285, which is also used by the EU Digital COVID
+//! Certificate.
+//!
+//! The alphabet is exactly the character set of the QR alphanumeric mode, so
+//! encoded payloads are stored with 5.5 bits per character and arrive as plain
+//! ASCII at the scanner, without any guessing of the text encoding.
+
+use crate::error::Error;
+
+const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+const BASE: u32 = 45;
+
+/// Encodes every two bytes as three characters and a trailing byte as two.
+pub fn encode(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len() / 2 * 3 + 2);
+    for chunk in data.chunks(2) {
+        let (mut value, digits) = match *chunk {
+            [high, low] => ((high as u32) << 8 | low as u32, 3),
+            [byte] => (byte as u32, 2),
+            _ => unreachable!(),
+        };
+        for _ in 0..digits {
+            text.push(ALPHABET[(value % BASE) as usize] as char);
+            value /= BASE;
+        }
+    }
+    text
+}
+
+/// Decodes Base45 text, failing on characters outside of the alphabet and on
+/// groups that don't encode a valid number of bytes.
+pub fn decode(text: &str) -> Result<Vec<u8>, Error> {
+    let digits = text
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|&digit| digit == c))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| Error::malformed("Invalid Base45 character."))?;
+    let mut data = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        let value = group
+            .iter()
+            .rev()
+            .fold(0, |value, &digit| value * BASE + digit as u32);
+        match group.len() {
+            3 if value <= 0xFFFF => data.extend_from_slice(&[(value >> 8) as u8, value as u8]),
+            2 if value <= 0xFF => data.push(value as u8),
+            _ => return Err(Error::malformed("Invalid Base45 length.")),
+        }
+    }
+    Ok(data)
+}
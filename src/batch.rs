@@ -0,0 +1,134 @@
+use crate::{
+    crypto::Signer,
+    error::Error,
+    metadata::Metadata,
+    payload::Payload,
+    qr_generator::{encode_data_as, Encoding},
+};
+use js_sys::Promise;
+use std::io::Write;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{SubtleCrypto, WorkerGlobalScope};
+use zip::{write::FileOptions, ZipWriter};
+
+/// Name of the report inside the archive listing lines that couldn't be signed.
+pub const ERRORS_FILE: &str = "errors.txt";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub signed: usize,
+    pub rendered: usize,
+    pub total: usize,
+}
+
+pub struct Signed {
+    pub zip: Vec<u8>,
+    /// Number of lines that are listed in `errors.txt` instead of having a code.
+    pub failed: usize,
+}
+
+/// Signs every line of `text` and returns a ZIP archive with one QR code per line.
+///
+/// Lines that fail are skipped and reported in `errors.txt` inside the archive.
+/// `metadata` is signed along with every line. `progress` is called after every
+/// signed and every rendered line. Returns `None` if `is_cancelled` returned
+/// true at some point, which is checked after yielding to the event loop
+/// before every line.
+pub async fn sign_batch(
+    subtle: &SubtleCrypto,
+    signer: &Signer,
+    text: &str,
+    metadata: &Metadata,
+    encoding: Encoding,
+    mut progress: impl FnMut(Progress),
+    is_cancelled: impl Fn() -> bool,
+) -> Result<Option<Signed>, Error> {
+    let lines: Vec<_> = text
+        .lines()
+        .map(|line| metadata.wrap(line.as_bytes()))
+        .collect();
+    let mut state = Progress {
+        total: lines.len(),
+        ..Progress::default()
+    };
+    let mut errors = Vec::new();
+
+    let mut signatures = Vec::with_capacity(lines.len());
+    for (idx, line) in lines.iter().enumerate() {
+        next_task().await?;
+        if is_cancelled() {
+            return Ok(None);
+        }
+        match signer.sign(subtle, line).await {
+            Ok(signature) => signatures.push(Some(signature)),
+            Err(err) => {
+                errors.push((idx, err.to_string()));
+                signatures.push(None);
+            }
+        }
+        state.signed = idx + 1;
+        progress(state);
+    }
+
+    let mut data: Vec<u8> = Vec::new();
+    {
+        let mut cursor = std::io::Cursor::new(&mut data);
+        let mut zip = ZipWriter::new(&mut cursor);
+        for (idx, (line, signature)) in lines.iter().zip(signatures).enumerate() {
+            next_task().await?;
+            if is_cancelled() {
+                return Ok(None);
+            }
+            if let Some(signature) = signature {
+                let signed_data = Payload::Signed {
+                    signature,
+                    data: line.clone(),
+                }
+                .to_bytes();
+
+                match encode_data_as(&signed_data, &encoding) {
+                    Ok(svg) => {
+                        zip.start_file(format!("signed_{}.svg", idx + 1), FileOptions::default())
+                            .map_err(zip_error)?;
+                        zip.write_all(svg.as_bytes()).map_err(zip_error)?;
+                    }
+                    Err(err) => errors.push((idx, err.to_string())),
+                }
+            }
+            state.rendered = idx + 1;
+            progress(state);
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|(idx, _)| *idx);
+            zip.start_file(ERRORS_FILE, FileOptions::default())
+                .map_err(zip_error)?;
+            for (idx, message) in &errors {
+                writeln!(zip, "Line {}: {}", idx + 1, message).map_err(zip_error)?;
+            }
+        }
+        zip.finish().map_err(zip_error)?;
+    }
+    Ok(Some(Signed {
+        zip: data,
+        failed: errors.len(),
+    }))
+}
+
+/// Waits for the next task of the worker's event loop, so that messages sent to
+/// it in the meantime are handled.
+async fn next_task() -> Result<(), Error> {
+    let scope = js_sys::global().unchecked_into::<WorkerGlobalScope>();
+    let mut scheduled = Ok(0);
+    let promise = Promise::new(&mut |resolve, _| {
+        scheduled = scope.set_timeout_with_callback(&resolve);
+    });
+    scheduled?;
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+fn zip_error(err: impl std::fmt::Display) -> Error {
+    Error::Archive(err.to_string())
+}
@@ -0,0 +1,61 @@
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Element, HtmlVideoElement, MediaStreamTrack};
+
+const STORAGE_KEY: &str = "qr-signing.camera";
+
+/// The video track of the camera feed rendered somewhere inside `container`.
+pub fn video_track(container: &Element) -> Option<MediaStreamTrack> {
+    let video: HtmlVideoElement = container.query_selector("video").ok()??.dyn_into().ok()?;
+    video
+        .src_object()?
+        .get_video_tracks()
+        .get(0)
+        .dyn_into()
+        .ok()
+}
+
+/// Torch control is not part of the standard constraints dictionary, so it's
+/// probed through the capabilities object directly.
+pub fn supports_torch(track: &MediaStreamTrack) -> bool {
+    Reflect::get(track, &JsValue::from_str("getCapabilities"))
+        .ok()
+        .and_then(|get_capabilities| get_capabilities.dyn_into::<Function>().ok())
+        .and_then(|get_capabilities| get_capabilities.call0(track).ok())
+        .and_then(|capabilities| Reflect::get(&capabilities, &JsValue::from_str("torch")).ok())
+        .map(|torch| torch.is_truthy())
+        .unwrap_or(false)
+}
+
+pub async fn set_torch(track: &MediaStreamTrack, on: bool) -> Result<(), JsValue> {
+    let torch = Object::new();
+    Reflect::set(&torch, &JsValue::from_str("torch"), &JsValue::from_bool(on))?;
+    let constraints = Object::new();
+    Reflect::set(
+        &constraints,
+        &JsValue::from_str("advanced"),
+        &Array::of1(&torch),
+    )?;
+    let apply_constraints: Function =
+        Reflect::get(track, &JsValue::from_str("applyConstraints"))?.dyn_into()?;
+    let promise: js_sys::Promise = apply_constraints.call1(track, &constraints)?.dyn_into()?;
+    JsFuture::from(promise).await.map(|_| ())
+}
+
+/// The camera that was selected in a previous session, if any.
+pub fn remembered_camera() -> Option<String> {
+    web_sys::window()?
+        .local_storage()
+        .ok()??
+        .get_item(STORAGE_KEY)
+        .ok()?
+}
+
+pub fn remember_camera(id: &str) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let _ = storage.set_item(STORAGE_KEY, id);
+    }
+}
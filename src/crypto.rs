@@ -1,39 +1,169 @@
+//! ECDSA P-256 through the browser's WebCrypto API.
+//!
+//! Signatures are in the raw (IEEE P1363) format and public keys are exported as
+//! uncompressed points, both as used in [`Payload`](crate::payload::Payload).
+//! Signatures from other tools are usually DER encoded,
+//! [`SignatureEncoding::to_raw`] converts them for verification.
+
+use crate::{
+    ecdsa,
+    error::Error,
+    fingerprint::{self, Fingerprint},
+    key_file::KeyFile,
+    payload,
+};
 use js_sys::{Array, ArrayBuffer, Object, Reflect};
-use serde::{Deserialize, Serialize};
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{CryptoKey, SubtleCrypto};
 
 const CURVE: &str = "P-256";
-pub const SIGNATURE_SIZE: usize = 64;
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EcKeyGenParams {
-    name: String,
-    named_curve: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EcdsaParams {
-    name: String,
-    hash: String,
-}
-
-pub async fn generate_keypair(subtle: &SubtleCrypto) -> Result<(CryptoKey, CryptoKey), JsValue> {
-    let key_pair = JsFuture::from(
-        subtle.generate_key_with_object(
-            &JsValue::from_serde(&EcKeyGenParams {
-                name: "ECDSA".to_owned(),
-                named_curve: CURVE.to_owned(),
-            })
-            .expect("Failed serializing options")
-            .into(),
-            true,
-            &Array::of2(&JsValue::from_str("sign"), &JsValue::from_str("verify")),
-        )?,
-    )
+
+/// Value of the `type` field identifying key pair files.
+const KEY_PAIR_FILE_TYPE: &str = "qr_key_pair";
+
+/// Tag of an ASN.1 SEQUENCE, which DER encoded signatures start with.
+const SEQUENCE: u8 = 0x30;
+/// Tag of an ASN.1 INTEGER, used for `r` and `s`.
+const INTEGER: u8 = 0x02;
+/// Size of `r` and `s` in raw signatures.
+const SCALAR_SIZE: usize = payload::SIGNATURE_SIZE / 2;
+
+/// How the two numbers `r` and `s` of an ECDSA signature are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Both as 32 byte big-endian numbers, as produced by WebCrypto.
+    Raw,
+    /// An ASN.1 SEQUENCE of two INTEGERs, as produced by OpenSSL, Java and
+    /// most HSMs.
+    Der,
+}
+
+impl SignatureEncoding {
+    /// Detects the encoding of a signature, `None` if it is neither.
+    pub fn detect(signature: &[u8]) -> Option<Self> {
+        if der_to_raw_signature(signature).is_ok() {
+            Some(Self::Der)
+        } else if signature.len() == payload::SIGNATURE_SIZE {
+            Some(Self::Raw)
+        } else {
+            None
+        }
+    }
+
+    /// Converts a signature in this encoding to the raw format.
+    pub fn to_raw(self, signature: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Raw if signature.len() == payload::SIGNATURE_SIZE => Ok(signature.to_vec()),
+            Self::Raw => Err(Error::malformed("Invalid signature.")),
+            Self::Der => der_to_raw_signature(signature),
+        }
+    }
+}
+
+/// Converts a DER encoded signature to the raw format.
+pub fn der_to_raw_signature(der: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::malformed("Invalid DER signature.");
+    let contents = match der {
+        [SEQUENCE, length, contents @ ..] if *length as usize == contents.len() => contents,
+        _ => return Err(invalid()),
+    };
+    let (r, rest) = der_integer(contents).ok_or_else(invalid)?;
+    let (s, rest) = der_integer(rest).ok_or_else(invalid)?;
+    if !rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut raw = vec![0; payload::SIGNATURE_SIZE];
+    raw[SCALAR_SIZE - r.len()..SCALAR_SIZE].copy_from_slice(r);
+    raw[payload::SIGNATURE_SIZE - s.len()..].copy_from_slice(s);
+    Ok(raw)
+}
+
+/// Splits a positive INTEGER of at most [`SCALAR_SIZE`] bytes off `der`,
+/// returning its value without leading zeros.
+fn der_integer(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (length, rest) = match der {
+        [INTEGER, length, rest @ ..] => (*length as usize, rest),
+        _ => return None,
+    };
+    if length == 0 || rest.len() < length || rest[0] & 0x80 != 0 {
+        return None;
+    }
+    let (value, rest) = rest.split_at(length);
+    let start = value.iter().position(|&byte| byte != 0).unwrap_or(length);
+    let value = &value[start..];
+    if value.len() > SCALAR_SIZE {
+        return None;
+    }
+    Some((value, rest))
+}
+
+/// Converts a raw signature to DER.
+pub fn raw_to_der_signature(raw: &[u8]) -> Result<Vec<u8>, Error> {
+    if raw.len() != payload::SIGNATURE_SIZE {
+        return Err(Error::malformed("Invalid signature."));
+    }
+    let mut contents = Vec::new();
+    for scalar in raw.chunks(SCALAR_SIZE) {
+        let start = scalar
+            .iter()
+            .position(|&byte| byte != 0)
+            .unwrap_or(SCALAR_SIZE);
+        let value = &scalar[start..];
+        // INTEGERs are signed, positive numbers with the top bit set need a zero in front.
+        let padding = value.is_empty() || value[0] & 0x80 != 0;
+        contents.push(INTEGER);
+        contents.push((value.len() + padding as usize) as u8);
+        if padding {
+            contents.push(0);
+        }
+        contents.extend_from_slice(value);
+    }
+    Ok([&[SEQUENCE, contents.len() as u8], contents.as_slice()].concat())
+}
+
+/// Returns WebCrypto of the current window or worker.
+///
+/// Browsers only offer it in secure contexts, i.e. over HTTPS or on localhost.
+pub fn subtle() -> Result<SubtleCrypto, Error> {
+    let crypto = match web_sys::window() {
+        Some(window) => window.crypto(),
+        None => js_sys::global()
+            .unchecked_into::<web_sys::WorkerGlobalScope>()
+            .crypto(),
+    };
+    let subtle = crypto
+        .map_err(|_| Error::Unavailable("WebCrypto"))?
+        .subtle();
+    if subtle.is_undefined() {
+        return Err(Error::Unavailable("WebCrypto"));
+    }
+    Ok(subtle)
+}
+
+/// Parameters for generating and importing keys.
+fn key_algorithm() -> Result<Object, JsValue> {
+    let algorithm = Object::new();
+    Reflect::set(&algorithm, &"name".into(), &"ECDSA".into())?;
+    Reflect::set(&algorithm, &"namedCurve".into(), &CURVE.into())?;
+    Ok(algorithm)
+}
+
+/// Parameters for signing and verifying.
+fn signature_algorithm() -> Result<Object, JsValue> {
+    let algorithm = Object::new();
+    Reflect::set(&algorithm, &"name".into(), &"ECDSA".into())?;
+    Reflect::set(&algorithm, &"hash".into(), &"SHA-256".into())?;
+    Ok(algorithm)
+}
+
+/// Generates an extractable key pair, returned as `(public, private)`.
+pub async fn generate_keypair(subtle: &SubtleCrypto) -> Result<(CryptoKey, CryptoKey), Error> {
+    let key_pair = JsFuture::from(subtle.generate_key_with_object(
+        &key_algorithm()?,
+        true,
+        &Array::of2(&JsValue::from_str("sign"), &JsValue::from_str("verify")),
+    )?)
     .await?;
     let public_key: CryptoKey =
         Reflect::get(&key_pair, &JsValue::from_str("publicKey"))?.unchecked_into();
@@ -43,131 +173,360 @@ pub async fn generate_keypair(subtle: &SubtleCrypto) -> Result<(CryptoKey, Crypt
     Ok((public_key, private_key))
 }
 
-pub async fn export_key(subtle: &SubtleCrypto, key: &CryptoKey) -> Result<Object, JsValue> {
+/// Exports a key as JWK.
+pub async fn export_key(subtle: &SubtleCrypto, key: &CryptoKey) -> Result<Object, Error> {
     let key_data = JsFuture::from(subtle.export_key("jwk", key)?).await?;
     Ok(key_data.unchecked_into())
 }
 
-pub async fn export_key_raw(
-    subtle: &SubtleCrypto,
-    key: &CryptoKey,
-) -> Result<ArrayBuffer, JsValue> {
+/// Exports a public key as an uncompressed point.
+pub async fn export_key_raw(subtle: &SubtleCrypto, key: &CryptoKey) -> Result<ArrayBuffer, Error> {
     let key_data = JsFuture::from(subtle.export_key("raw", key)?).await?;
     Ok(key_data.unchecked_into())
 }
 
+/// Returns the fingerprint of a public key.
+pub async fn fingerprint_of(
+    subtle: &SubtleCrypto,
+    public_key: &CryptoKey,
+) -> Result<Fingerprint, Error> {
+    let raw = export_key_raw(subtle, public_key).await?;
+    Ok(fingerprint::fingerprint(
+        &js_sys::Uint8Array::new(&raw).to_vec(),
+    ))
+}
+
+/// Signs `data`, returning the raw 64 byte signature.
 pub async fn sign(
     subtle: &SubtleCrypto,
     private_key: &CryptoKey,
-    text: &str,
-) -> Result<ArrayBuffer, JsValue> {
-    let mut text = text.to_owned();
-
-    let signed_bytes = JsFuture::from(
-        subtle.sign_with_object_and_u8_array(
-            &JsValue::from_serde(&EcdsaParams {
-                name: "ECDSA".to_owned(),
-                hash: "SHA-256".to_owned(),
-            })
-            .expect("Failed serializing options")
-            .into(),
-            &private_key,
-            unsafe { text.as_bytes_mut() },
-        )?,
-    )
+    data: &[u8],
+) -> Result<ArrayBuffer, Error> {
+    let signed_bytes = JsFuture::from(subtle.sign_with_object_and_u8_array(
+        &signature_algorithm()?,
+        private_key,
+        data,
+    )?)
     .await?;
 
     Ok(signed_bytes.unchecked_into())
 }
 
+/// Makes signatures either through WebCrypto or in Rust.
+///
+/// WebCrypto picks a random nonce for every signature, so signing the same data
+/// twice gives different codes. [`ecdsa`] derives the nonce from key and data
+/// as specified in RFC 6979 instead, which makes signatures reproducible.
+pub enum Signer {
+    WebCrypto(CryptoKey),
+    Deterministic(ecdsa::KeyPair),
+}
+
+impl Signer {
+    /// Creates a signer for `private_key`, which has to be extractable for
+    /// deterministic signatures.
+    pub async fn new(
+        subtle: &SubtleCrypto,
+        private_key: &CryptoKey,
+        deterministic: bool,
+    ) -> Result<Self, Error> {
+        if !deterministic {
+            return Ok(Self::WebCrypto(private_key.clone()));
+        }
+        let jwk = export_key(subtle, private_key).await?;
+        let d = Reflect::get(&jwk, &JsValue::from_str("d"))?
+            .as_string()
+            .ok_or_else(|| Error::WrongKeyType("This is not a private key.".to_owned()))?;
+        let d = base64::decode_config(&d, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| Error::malformed("Invalid private key."))?;
+        Ok(Self::Deterministic(ecdsa::KeyPair::from_private_key(&d)?))
+    }
+
+    /// Signs `data`, returning the raw 64 byte signature.
+    pub async fn sign(&self, subtle: &SubtleCrypto, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::WebCrypto(private_key) => {
+                let signature = sign(subtle, private_key, data).await?;
+                Ok(js_sys::Uint8Array::new(&signature).to_vec())
+            }
+            Self::Deterministic(key_pair) => Ok(key_pair.sign(data)),
+        }
+    }
+}
+
+/// Imports a public key from a JWK.
 pub async fn import_public_key(
     subtle: &SubtleCrypto,
     key: &js_sys::Object,
-) -> Result<CryptoKey, JsValue> {
-    wasm_bindgen_futures::JsFuture::from(
-        subtle.import_key_with_object(
-            "jwk",
-            key,
-            JsValue::from_serde(&EcKeyGenParams {
-                name: "ECDSA".to_owned(),
-                named_curve: CURVE.to_owned(),
-            })
-            .unwrap()
-            .unchecked_ref(),
-            true,
-            &Array::of1(&JsValue::from_str("verify")),
-        )?,
+) -> Result<CryptoKey, Error> {
+    import_key(subtle, "jwk", key, "verify").await
+}
+
+/// Imports a public key from a point, as found in `PUB:` codes.
+pub async fn import_public_key_raw(subtle: &SubtleCrypto, key: &[u8]) -> Result<CryptoKey, Error> {
+    // Not all browsers import compressed points.
+    let key = ecdsa::decompress_public_key(key)?;
+    import_key(
+        subtle,
+        "raw",
+        &js_sys::Uint8Array::from(key.as_slice()),
+        "verify",
     )
     .await
-    .map(|key| key.unchecked_into())
 }
 
-pub async fn import_public_key_raw(
+/// Imports a public key from DER encoded SubjectPublicKeyInfo.
+pub async fn import_public_key_spki(subtle: &SubtleCrypto, key: &[u8]) -> Result<CryptoKey, Error> {
+    import_key(subtle, "spki", &js_sys::Uint8Array::from(key), "verify").await
+}
+
+/// Imports a private key from a JWK.
+pub async fn import_private_key(
+    subtle: &SubtleCrypto,
+    key: &js_sys::Object,
+) -> Result<CryptoKey, Error> {
+    import_key(subtle, "jwk", key, "sign").await
+}
+
+/// Imports a private key from DER encoded PKCS#8.
+pub async fn import_private_key_pkcs8(
     subtle: &SubtleCrypto,
     key: &[u8],
-) -> Result<CryptoKey, JsValue> {
-    let u8array = js_sys::Uint8Array::from(key);
-    wasm_bindgen_futures::JsFuture::from(
-        subtle.import_key_with_object(
-            "raw",
-            &u8array,
-            JsValue::from_serde(&EcKeyGenParams {
-                name: "ECDSA".to_owned(),
-                named_curve: CURVE.to_owned(),
-            })
-            .unwrap()
-            .unchecked_ref(),
-            true,
-            &Array::of1(&JsValue::from_str("verify")),
-        )?,
-    )
-    .await
-    .map(|key| key.unchecked_into())
+) -> Result<CryptoKey, Error> {
+    import_key(subtle, "pkcs8", &js_sys::Uint8Array::from(key), "sign").await
 }
 
-pub async fn import_private_key(
+async fn import_key(
     subtle: &SubtleCrypto,
+    format: &str,
     key: &js_sys::Object,
-) -> Result<CryptoKey, JsValue> {
-    wasm_bindgen_futures::JsFuture::from(
-        subtle.import_key_with_object(
-            "jwk",
-            key,
-            JsValue::from_serde(&EcKeyGenParams {
-                name: "ECDSA".to_owned(),
-                named_curve: CURVE.to_owned(),
-            })
-            .unwrap()
-            .unchecked_ref(),
-            true,
-            &Array::of1(&JsValue::from_str("sign")),
-        )?,
-    )
+    usage: &str,
+) -> Result<CryptoKey, Error> {
+    wasm_bindgen_futures::JsFuture::from(subtle.import_key_with_object(
+        format,
+        key,
+        &key_algorithm()?,
+        true,
+        &Array::of1(&JsValue::from_str(usage)),
+    )?)
     .await
     .map(|key| key.unchecked_into())
+    .map_err(Error::from)
+}
+
+/// Exports a public key as DER encoded SubjectPublicKeyInfo.
+pub async fn export_key_spki(subtle: &SubtleCrypto, key: &CryptoKey) -> Result<Vec<u8>, Error> {
+    let key_data = JsFuture::from(subtle.export_key("spki", key)?).await?;
+    Ok(js_sys::Uint8Array::new(&key_data).to_vec())
+}
+
+/// Exports a private key as DER encoded PKCS#8.
+pub async fn export_key_pkcs8(subtle: &SubtleCrypto, key: &CryptoKey) -> Result<Vec<u8>, Error> {
+    let key_data = JsFuture::from(subtle.export_key("pkcs8", key)?).await?;
+    Ok(js_sys::Uint8Array::new(&key_data).to_vec())
 }
 
+/// Derives the public key of a private key, for key files that only contain the
+/// latter.
+pub async fn public_key_of(
+    subtle: &SubtleCrypto,
+    private_key: &CryptoKey,
+) -> Result<CryptoKey, Error> {
+    // The JWK of a private key contains the public point as well.
+    let jwk = export_key(subtle, private_key).await?;
+    Reflect::delete_property(&jwk, &JsValue::from_str("d"))?;
+    Reflect::set(
+        &jwk,
+        &JsValue::from_str("key_ops"),
+        &Array::of1(&JsValue::from_str("verify")),
+    )?;
+    import_public_key(subtle, &jwk).await
+}
+
+/// Checks a raw signature over `data`.
 pub async fn verify(
     subtle: &SubtleCrypto,
     public_key: &CryptoKey,
     signature: &[u8],
     data: &[u8],
-) -> Result<bool, JsValue> {
-    let mut signature = signature.to_vec();
-    let mut data = data.to_vec();
-    wasm_bindgen_futures::JsFuture::from(
-        subtle.verify_with_object_and_u8_array_and_u8_array(
-            JsValue::from_serde(&EcdsaParams {
-                name: "ECDSA".to_owned(),
-                hash: "SHA-256".to_owned(),
-            })
-            .unwrap()
-            .unchecked_ref(),
-            public_key,
-            &mut signature,
-            &mut data,
-        )?,
-    )
+) -> Result<bool, Error> {
+    wasm_bindgen_futures::JsFuture::from(subtle.verify_with_object_and_u8_array_and_u8_array(
+        &signature_algorithm()?,
+        public_key,
+        signature,
+        data,
+    )?)
     .await
     .map(|flag| flag.is_truthy())
+    .map_err(Error::from)
+}
+
+/// Signs the rotation from the old to the new key pair with both private keys,
+/// returning the `ROT:` payload.
+pub async fn sign_rotation(
+    subtle: &SubtleCrypto,
+    old_public_key: &CryptoKey,
+    old_private_key: &CryptoKey,
+    new_public_key: &CryptoKey,
+    new_private_key: &CryptoKey,
+) -> Result<payload::Payload, Error> {
+    let old_key = export_key_raw(subtle, old_public_key).await?;
+    let old_key = js_sys::Uint8Array::new(&old_key).to_vec();
+    let new_key = export_key_raw(subtle, new_public_key).await?;
+    let new_key = js_sys::Uint8Array::new(&new_key).to_vec();
+    let message = payload::rotation_message(&old_key, &new_key);
+    let old_signature = sign(subtle, old_private_key, &message).await?;
+    let new_signature = sign(subtle, new_private_key, &message).await?;
+    Ok(payload::Payload::Rotation {
+        old_key,
+        new_key,
+        old_signature: js_sys::Uint8Array::new(&old_signature).to_vec(),
+        new_signature: js_sys::Uint8Array::new(&new_signature).to_vec(),
+    })
+}
+
+/// Adds the signature of a key pair to a multi-signature code.
+pub async fn cosign(
+    subtle: &SubtleCrypto,
+    public_key: &CryptoKey,
+    signer: &Signer,
+    payload: &mut payload::Payload,
+) -> Result<(), Error> {
+    let data = match payload {
+        payload::Payload::MultiSigned { data, .. } => data.clone(),
+        _ => return Err(Error::malformed("This code can't be co-signed.")),
+    };
+    let key_id = payload::key_id(&fingerprint_of(subtle, public_key).await?);
+    let signature = signer
+        .sign(subtle, &payload::multi_signed_message(&data))
+        .await?;
+    payload.add_signature(payload::Cosignature { key_id, signature })
+}
+
+/// Checks both signatures of a key rotation and imports the new key.
+///
+/// Returns `None` if a signature doesn't match. Whether the old key is trusted
+/// is up to the caller.
+pub async fn verify_rotation(
+    subtle: &SubtleCrypto,
+    old_key: &[u8],
+    new_key: &[u8],
+    old_signature: &[u8],
+    new_signature: &[u8],
+) -> Result<Option<CryptoKey>, Error> {
+    let message = payload::rotation_message(old_key, new_key);
+    let old_public_key = import_public_key_raw(subtle, old_key).await?;
+    let new_public_key = import_public_key_raw(subtle, new_key).await?;
+    if verify(subtle, &old_public_key, old_signature, &message).await?
+        && verify(subtle, &new_public_key, new_signature, &message).await?
+    {
+        Ok(Some(new_public_key))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Serializes a key pair to the JSON key file format of the app.
+pub async fn export_key_pair_file(
+    subtle: &SubtleCrypto,
+    public_key: &CryptoKey,
+    private_key: &CryptoKey,
+) -> Result<String, Error> {
+    let public_key = export_key(subtle, public_key).await?;
+    let private_key = export_key(subtle, private_key).await?;
+
+    let key_pair_json = Object::new();
+    Reflect::set(&key_pair_json, &JsValue::from_str("public"), &public_key)?;
+    Reflect::set(&key_pair_json, &JsValue::from_str("private"), &private_key)?;
+    Reflect::set(
+        &key_pair_json,
+        &JsValue::from_str("type"),
+        &JsValue::from_str(KEY_PAIR_FILE_TYPE),
+    )?;
+
+    Ok(js_sys::JSON::stringify(&key_pair_json)?
+        .as_string()
+        .unwrap_or_default())
+}
+
+/// Reads a key file as written by [`export_key_pair_file`], returned as
+/// `(public, private)`.
+pub async fn import_key_pair_file(
+    subtle: &SubtleCrypto,
+    text: &str,
+) -> Result<(CryptoKey, CryptoKey), Error> {
+    let not_a_key_pair = || Error::malformed("This file is not a key pair!");
+
+    let json = js_sys::JSON::parse(text).map_err(|_| not_a_key_pair())?;
+    if !json.is_object()
+        || Reflect::get(&json, &JsValue::from_str("type"))?
+            .as_string()
+            .as_deref()
+            != Some(KEY_PAIR_FILE_TYPE)
+    {
+        return Err(not_a_key_pair());
+    }
+    let public = Reflect::get(&json, &JsValue::from_str("public"))?;
+    let private = Reflect::get(&json, &JsValue::from_str("private"))?;
+    if !public.is_object() || !private.is_object() {
+        return Err(not_a_key_pair());
+    }
+    let public_key = import_public_key(subtle, public.unchecked_ref()).await?;
+    let private_key = import_private_key(subtle, private.unchecked_ref()).await?;
+    Ok((public_key, private_key))
+}
+
+/// Imports the keys of a key file in any supported format, returned as
+/// `(public, private)`. Files with only a public key have no private key.
+pub async fn import_key_file(
+    subtle: &SubtleCrypto,
+    key_file: &KeyFile,
+) -> Result<(CryptoKey, Option<CryptoKey>), Error> {
+    match key_file {
+        KeyFile::Json(text) => {
+            let json = js_sys::JSON::parse(text)
+                .map_err(|_| Error::malformed("This file is not a key."))?;
+            if Reflect::has(&json, &JsValue::from_str("kty")).unwrap_or(false) {
+                import_jwk(subtle, json.unchecked_ref()).await
+            } else {
+                let (public_key, private_key) = import_key_pair_file(subtle, text).await?;
+                Ok((public_key, Some(private_key)))
+            }
+        }
+        KeyFile::Der {
+            private_key: Some(private_key),
+            public_key,
+        } => {
+            let private_key = import_private_key_pkcs8(subtle, private_key).await?;
+            let public_key = match public_key {
+                Some(public_key) => import_public_key_spki(subtle, public_key).await?,
+                None => public_key_of(subtle, &private_key).await?,
+            };
+            Ok((public_key, Some(private_key)))
+        }
+        KeyFile::Der {
+            private_key: None,
+            public_key: Some(public_key),
+        } => Ok((import_public_key_spki(subtle, public_key).await?, None)),
+        KeyFile::Der { .. } => Err(Error::malformed("The file contains no key.")),
+        KeyFile::RawPublicKey(public_key) => {
+            Ok((import_public_key_raw(subtle, public_key).await?, None))
+        }
+    }
+}
+
+/// Imports a single JWK, which may be a private or a public key.
+async fn import_jwk(
+    subtle: &SubtleCrypto,
+    jwk: &Object,
+) -> Result<(CryptoKey, Option<CryptoKey>), Error> {
+    if Reflect::has(jwk, &JsValue::from_str("d"))? {
+        let private_key = import_private_key(subtle, jwk).await?;
+        Ok((
+            public_key_of(subtle, &private_key).await?,
+            Some(private_key),
+        ))
+    } else {
+        Ok((import_public_key(subtle, jwk).await?, None))
+    }
 }
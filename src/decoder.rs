@@ -0,0 +1,31 @@
+/// Converts RGBA pixels (as found in canvas `ImageData`) to 8 bit luminance.
+pub fn rgba_to_luma(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .map(|pixel| {
+            let luma =
+                299 * u32::from(pixel[0]) + 587 * u32::from(pixel[1]) + 114 * u32::from(pixel[2]);
+            (luma / 1000) as u8
+        })
+        .collect()
+}
+
+/// Decodes all QR codes found in a greyscale image of `width` × `height` pixels.
+///
+/// Codes that are detected but can't be read completely are skipped. Mirrored
+/// codes, as delivered by most front cameras, are read as well.
+pub fn decode_luma(width: usize, height: usize, luma: &[u8]) -> Vec<Vec<u8>> {
+    if width == 0 || height == 0 || luma.len() < width * height {
+        return Vec::new();
+    }
+    let mut image =
+        rqrr::PreparedImage::prepare_from_greyscale(width, height, |x, y| luma[y * width + x]);
+    image
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| {
+            let mut data = Vec::new();
+            grid.decode_to(&mut data).ok()?;
+            Some(data)
+        })
+        .collect()
+}
@@ -0,0 +1,211 @@
+//! ECDSA P-256 in pure Rust, for use outside of the browser.
+//!
+//! Keys and signatures are interchangeable with the ones produced by the
+//! WebCrypto functions in [`crypto`](crate::crypto).
+
+use crate::{
+    error::Error,
+    fingerprint,
+    payload::{self, Cosignature, Payload},
+};
+use p256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use rand_core::OsRng;
+
+/// A private key together with its public key.
+#[derive(Clone, Debug)]
+pub struct KeyPair {
+    signing_key: SigningKey,
+}
+
+impl KeyPair {
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::random(&mut OsRng),
+        }
+    }
+
+    /// Creates the key pair from the 32 byte private scalar, which is the `d`
+    /// parameter of a JWK.
+    pub fn from_private_key(private_key: &[u8]) -> Result<Self, Error> {
+        SigningKey::from_slice(private_key)
+            .map(|signing_key| Self { signing_key })
+            .map_err(|_| Error::malformed("Invalid private key."))
+    }
+
+    pub fn private_key(&self) -> Vec<u8> {
+        self.signing_key.to_bytes().to_vec()
+    }
+
+    /// The public key as uncompressed point, see [`compress_public_key`] for
+    /// `PUB:` codes.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signing_key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    /// Signs `data` with SHA-256, returning the raw 64 byte signature.
+    pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+        let signature: Signature = self.signing_key.sign(data);
+        signature.to_bytes().to_vec()
+    }
+
+    /// Signs `data` and wraps it into a payload ready for encoding.
+    pub fn sign_payload(&self, data: &[u8]) -> Payload {
+        Payload::Signed {
+            signature: self.sign(data),
+            data: data.to_vec(),
+        }
+    }
+
+    /// Signs the hash and name of a file for a detached signature.
+    pub fn sign_file_payload(&self, name: &str, contents: &[u8]) -> Payload {
+        let hash = payload::file_hash(contents);
+        Payload::File {
+            signature: self.sign(&payload::file_message(&hash, name)),
+            hash,
+            name: name.to_owned(),
+        }
+    }
+
+    /// Starts a multi-signature code for `data` with the signature of this key.
+    pub fn sign_multi_payload(&self, data: &[u8]) -> Payload {
+        Payload::MultiSigned {
+            signatures: vec![self.cosign_data(data)],
+            data: data.to_vec(),
+        }
+    }
+
+    /// Adds the signature of this key to a multi-signature code.
+    pub fn cosign(&self, payload: &mut Payload) -> Result<(), Error> {
+        let cosignature = match payload {
+            Payload::MultiSigned { data, .. } => self.cosign_data(data),
+            _ => return Err(Error::malformed("This code can't be co-signed.")),
+        };
+        payload.add_signature(cosignature)
+    }
+
+    fn cosign_data(&self, data: &[u8]) -> Cosignature {
+        Cosignature {
+            key_id: payload::key_id(&fingerprint::fingerprint(&self.public_key())),
+            signature: self.sign(&payload::multi_signed_message(data)),
+        }
+    }
+
+    /// Announces `successor` as replacement of this key, signed by both keys.
+    pub fn rotation_payload(&self, successor: &KeyPair) -> Payload {
+        let old_key = self.public_key();
+        let new_key = successor.public_key();
+        let message = payload::rotation_message(&old_key, &new_key);
+        Payload::Rotation {
+            old_signature: self.sign(&message),
+            new_signature: successor.sign(&message),
+            old_key,
+            new_key,
+        }
+    }
+}
+
+/// Converts a public key to the 33 byte compressed point, which only stores
+/// the sign of `y`. `PUB:` codes use this form to keep the QR code small.
+pub fn compress_public_key(public_key: &[u8]) -> Result<Vec<u8>, Error> {
+    encode_point(public_key, true)
+}
+
+/// Converts a compressed or uncompressed public key to the 65 byte
+/// uncompressed point, which fingerprints are taken of.
+pub fn decompress_public_key(public_key: &[u8]) -> Result<Vec<u8>, Error> {
+    encode_point(public_key, false)
+}
+
+fn encode_point(public_key: &[u8], compress: bool) -> Result<Vec<u8>, Error> {
+    VerifyingKey::from_sec1_bytes(public_key)
+        .map(|key| key.to_encoded_point(compress).as_bytes().to_vec())
+        .map_err(|_| Error::malformed("Invalid public key."))
+}
+
+/// Checks a raw signature over `data`.
+///
+/// Fails if the key or signature can't be decoded, returns `false` if they can
+/// but the signature doesn't match.
+pub fn verify(public_key: &[u8], signature: &[u8], data: &[u8]) -> Result<bool, Error> {
+    let public_key = VerifyingKey::from_sec1_bytes(public_key)
+        .map_err(|_| Error::malformed("Invalid public key."))?;
+    let signature =
+        Signature::from_slice(signature).map_err(|_| Error::malformed("Invalid signature."))?;
+    Ok(public_key.verify(data, &signature).is_ok())
+}
+
+/// Checks a signed payload, see [`verify`].
+///
+/// For files only the signature over hash and name is checked, compare the hash
+/// with [`payload::file_hash`] of the file as well.
+pub fn verify_payload(public_key: &[u8], payload: &Payload) -> Result<bool, Error> {
+    payload.check_assertion()?;
+    match (payload.raw_signature()?, payload.signed_message()) {
+        (Some(signature), Some(message)) => verify(public_key, &signature, &message),
+        _ => Err(Error::malformed("This code is not signed.")),
+    }
+}
+
+/// Checks both signatures of a key rotation.
+///
+/// This only proves that the holder of the old key endorsed the new one, the
+/// old key still has to be trusted.
+pub fn verify_rotation(payload: &Payload) -> Result<bool, Error> {
+    match payload {
+        Payload::Rotation {
+            old_key,
+            new_key,
+            old_signature,
+            new_signature,
+        } => {
+            let message = payload::rotation_message(old_key, new_key);
+            Ok(verify(old_key, old_signature, &message)?
+                && verify(new_key, new_signature, &message)?)
+        }
+        _ => Err(Error::malformed("This code is not a key rotation.")),
+    }
+}
+
+/// Returns the indices of the `public_keys` with a valid signature in a
+/// multi-signature code. Every key is counted once.
+///
+/// Fails only if the payload is not a multi-signature code.
+pub fn valid_signers(public_keys: &[Vec<u8>], payload: &Payload) -> Result<Vec<usize>, Error> {
+    let (signatures, data) = match payload {
+        Payload::MultiSigned { signatures, data } => (signatures, data),
+        _ => return Err(Error::malformed("This code is not multi-signed.")),
+    };
+    let message = payload::multi_signed_message(data);
+    let mut signers = Vec::new();
+    for (index, public_key) in public_keys.iter().enumerate() {
+        let key_id = payload::key_id(&fingerprint::fingerprint(public_key));
+        for cosignature in signatures
+            .iter()
+            .filter(|cosignature| cosignature.key_id == key_id)
+        {
+            // A broken signature of one key doesn't spoil the others.
+            if verify(public_key, &cosignature.signature, &message).unwrap_or(false) {
+                signers.push(index);
+                break;
+            }
+        }
+    }
+    Ok(signers)
+}
+
+/// Checks that at least `threshold` of the `public_keys` signed a
+/// multi-signature code, e.g. two of three department keys.
+pub fn verify_threshold(
+    public_keys: &[Vec<u8>],
+    threshold: usize,
+    payload: &Payload,
+) -> Result<bool, Error> {
+    Ok(valid_signers(public_keys, payload)?.len() >= threshold)
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A WebCrypto operation failed for a reason not covered below.
+    Crypto(String),
+    /// A scanned code or imported file doesn't have the expected structure.
+    MalformedPayload(String),
+    /// A key or signature uses something other than ECDSA on P-256.
+    UnsupportedAlgorithm(String),
+    /// The data doesn't fit into a single QR code.
+    QrCapacityExceeded,
+    /// A file couldn't be read or isn't in the expected encoding.
+    FileDecoding(String),
+    /// A key was used for something it can't do, like a public key for signing.
+    WrongKeyType(String),
+    /// Writing an archive of signed codes failed.
+    Archive(String),
+    /// The browser lacks something the operation needs, like WebCrypto outside
+    /// of secure (HTTPS) contexts.
+    Unavailable(&'static str),
+}
+
+impl Error {
+    pub fn malformed(message: impl Into<String>) -> Self {
+        Self::MalformedPayload(message.into())
+    }
+
+    /// Converts an exception thrown by a JavaScript API, using the `DOMException`
+    /// name to tell the WebCrypto failure cases apart.
+    pub fn from_js(err: &JsValue) -> Self {
+        let message = js_message(err);
+        let name = err
+            .dyn_ref::<js_sys::Error>()
+            .map(|err| String::from(err.name()));
+        match name.as_deref() {
+            Some("NotSupportedError") => Self::UnsupportedAlgorithm(message),
+            Some("InvalidAccessError") => Self::WrongKeyType(message),
+            Some("DataError") | Some("SyntaxError") => Self::MalformedPayload(message),
+            _ => Self::Crypto(message),
+        }
+    }
+
+    /// Shows the error to the user.
+    pub fn report(&self) {
+        web_sys::console::log_1(&JsValue::from_str(&format!("{:?}", self)));
+        if let Some(window) = web_sys::window() {
+            let _ = window.alert_with_message(&self.to_string());
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crypto(message) => write!(f, "Cryptographic operation failed: {}", message),
+            Self::MalformedPayload(message) => write!(f, "Invalid data: {}", message),
+            Self::UnsupportedAlgorithm(message) => write!(
+                f,
+                "Unsupported algorithm, only ECDSA with P-256 is supported: {}",
+                message
+            ),
+            Self::QrCapacityExceeded => write!(f, "The data is too long for a QR code."),
+            Self::FileDecoding(message) => write!(f, "Could not read the file: {}", message),
+            Self::WrongKeyType(message) => write!(f, "Wrong kind of key: {}", message),
+            Self::Archive(message) => write!(f, "Could not create the archive: {}", message),
+            Self::Unavailable(what) => write!(f, "Not available in this browser: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<JsValue> for Error {
+    fn from(err: JsValue) -> Self {
+        Self::from_js(&err)
+    }
+}
+
+impl From<Error> for JsValue {
+    fn from(err: Error) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+fn js_message(err: &JsValue) -> String {
+    if let Some(message) = err.as_string() {
+        return message;
+    }
+    match err.dyn_ref::<js_sys::Error>() {
+        Some(err) => err.message().into(),
+        None => format!("{:?}", err),
+    }
+}
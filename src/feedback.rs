@@ -0,0 +1,59 @@
+use web_sys::{AudioContext, OscillatorType};
+
+const SUCCESS_TONES: &[(f32, f64)] = &[(880.0, 0.12), (1320.0, 0.18)];
+const FAILURE_TONES: &[(f32, f64)] = &[(220.0, 0.25), (180.0, 0.35)];
+
+/// Audible feedback for unattended verification stations.
+#[derive(Default)]
+pub struct Feedback {
+    context: Option<AudioContext>,
+}
+
+impl Feedback {
+    pub fn success(&mut self) {
+        self.play(SUCCESS_TONES);
+    }
+
+    pub fn failure(&mut self) {
+        self.play(FAILURE_TONES);
+    }
+
+    fn play(&mut self, tones: &[(f32, f64)]) {
+        if self.context.is_none() {
+            self.context = AudioContext::new().ok();
+        }
+        let context = match &self.context {
+            Some(context) => context,
+            None => return,
+        };
+        // Browsers start the context suspended until there was some user interaction.
+        let _ = context.resume();
+
+        let mut start = context.current_time();
+        for &(frequency, duration) in tones {
+            if let Err(err) = Self::tone(context, frequency, start, duration) {
+                web_sys::console::log_2(&wasm_bindgen::JsValue::from_str("AUDIO ERROR"), &err);
+                return;
+            }
+            start += duration;
+        }
+    }
+
+    fn tone(
+        context: &AudioContext,
+        frequency: f32,
+        start: f64,
+        duration: f64,
+    ) -> Result<(), wasm_bindgen::JsValue> {
+        let oscillator = context.create_oscillator()?;
+        let gain = context.create_gain()?;
+        oscillator.set_type(OscillatorType::Square);
+        oscillator.frequency().set_value(frequency);
+        gain.gain().set_value(0.2);
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+        oscillator.start_with_when(start)?;
+        oscillator.stop_with_when(start + duration)?;
+        Ok(())
+    }
+}
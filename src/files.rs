@@ -0,0 +1,65 @@
+//! Reading files chosen by the user and offering files for download.
+
+use crate::error::Error;
+use std::io::Write;
+use wasm_bindgen::JsCast;
+use web_sys::Url;
+use yew::NodeRef;
+
+/// Returns the file chosen in a file input and resets the input, so that
+/// choosing the same file again triggers another change event.
+pub fn selected_file(input: &NodeRef) -> Option<web_sys::File> {
+    let input = input.cast::<web_sys::HtmlInputElement>()?;
+    let file = input.files()?.get(0);
+    input.set_value("");
+    file
+}
+
+pub async fn read_file(file: &web_sys::File) -> Result<Vec<u8>, Error> {
+    let data = wasm_bindgen_futures::JsFuture::from(file.array_buffer())
+        .await
+        .map_err(|err| Error::FileDecoding(Error::from_js(&err).to_string()))?;
+    Ok(js_sys::Uint8Array::new(&data).to_vec())
+}
+
+pub fn download(data: &[u8], file_name: &str) -> Result<(), Error> {
+    let buffer = js_sys::Uint8Array::from(data);
+    let blob = web_sys::Blob::new_with_blob_sequence(&js_sys::Array::of1(&buffer))?;
+    let blob_url = Url::create_object_url_with_blob(&blob)?;
+    let result = download_url(&blob_url, file_name);
+    Url::revoke_object_url(&blob_url)?;
+    result
+}
+
+/// Packs files given as `(name, contents)` into a ZIP archive.
+///
+/// Browsers often block all but the first of several downloads started at once,
+/// so files that belong together are offered as one archive.
+pub fn zip(files: &[(&str, &[u8])]) -> Result<Vec<u8>, Error> {
+    let zip_error = |err: zip::result::ZipError| Error::Archive(err.to_string());
+    let mut data = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut data));
+        for (name, contents) in files {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .map_err(zip_error)?;
+            zip.write_all(contents)
+                .map_err(|err| Error::Archive(err.to_string()))?;
+        }
+        zip.finish().map_err(zip_error)?;
+    }
+    Ok(data)
+}
+
+/// Downloads the resource at `url`, which may be a `blob:` or `data:` URL.
+pub fn download_url(url: &str, file_name: &str) -> Result<(), Error> {
+    let a: web_sys::HtmlAnchorElement = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or(Error::Unavailable("document"))?
+        .create_element("A")?
+        .unchecked_into();
+    a.set_href(url);
+    a.set_download(file_name);
+    a.click();
+    Ok(())
+}
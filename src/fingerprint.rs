@@ -0,0 +1,151 @@
+//! Fingerprints identify public keys for comparison by humans. They are the
+//! SHA-256 hash of the raw (uncompressed) public key.
+
+pub const FINGERPRINT_SIZE: usize = 32;
+
+pub type Fingerprint = [u8; FINGERPRINT_SIZE];
+
+pub fn fingerprint(public_key: &[u8]) -> Fingerprint {
+    hmac_sha256::Hash::hash(public_key)
+}
+
+/// Formats a fingerprint as uppercase hex with colons, e.g. `3F:A2:…:7C`.
+pub fn to_hex(fingerprint: &Fingerprint) -> String {
+    fingerprint
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// Parses a fingerprint written as hex, with or without colons.
+pub fn parse_hex(text: &str) -> Option<Fingerprint> {
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|&c| c != ':')
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() != 2 * FINGERPRINT_SIZE {
+        return None;
+    }
+    let mut fingerprint = [0; FINGERPRINT_SIZE];
+    for (byte, pair) in fingerprint.iter_mut().zip(digits.chunks(2)) {
+        *byte = pair[0] << 4 | pair[1];
+    }
+    Some(fingerprint)
+}
+
+/// Number of emoji in the short form, 6 bits each.
+pub const EMOJI_COUNT: usize = 7;
+/// Number of bytes the emoji are taken from.
+const EMOJI_BYTES: usize = 6;
+/// Number of bytes in the short hex form.
+pub const SHORT_HEX_SIZE: usize = 4;
+
+/// The emoji of the Matrix short authentication strings with their names, so
+/// that they can be read out over the phone as well.
+pub const EMOJI: [(&str, &str); 64] = [
+    ("🐶", "Dog"),
+    ("🐱", "Cat"),
+    ("🦁", "Lion"),
+    ("🐎", "Horse"),
+    ("🦄", "Unicorn"),
+    ("🐷", "Pig"),
+    ("🐘", "Elephant"),
+    ("🐰", "Rabbit"),
+    ("🐼", "Panda"),
+    ("🐓", "Rooster"),
+    ("🐧", "Penguin"),
+    ("🐢", "Turtle"),
+    ("🐟", "Fish"),
+    ("🐙", "Octopus"),
+    ("🦋", "Butterfly"),
+    ("🌷", "Flower"),
+    ("🌳", "Tree"),
+    ("🌵", "Cactus"),
+    ("🍄", "Mushroom"),
+    ("🌏", "Globe"),
+    ("🌙", "Moon"),
+    ("☁️", "Cloud"),
+    ("🔥", "Fire"),
+    ("🍌", "Banana"),
+    ("🍎", "Apple"),
+    ("🍓", "Strawberry"),
+    ("🌽", "Corn"),
+    ("🍕", "Pizza"),
+    ("🎂", "Cake"),
+    ("❤️", "Heart"),
+    ("😀", "Smiley"),
+    ("🤖", "Robot"),
+    ("🎩", "Hat"),
+    ("👓", "Glasses"),
+    ("🔧", "Spanner"),
+    ("🎅", "Santa"),
+    ("👍", "Thumbs Up"),
+    ("☂️", "Umbrella"),
+    ("⌛", "Hourglass"),
+    ("⏰", "Clock"),
+    ("🎁", "Gift"),
+    ("💡", "Light Bulb"),
+    ("📕", "Book"),
+    ("✏️", "Pencil"),
+    ("📎", "Paperclip"),
+    ("✂️", "Scissors"),
+    ("🔒", "Lock"),
+    ("🔑", "Key"),
+    ("🔨", "Hammer"),
+    ("☎️", "Telephone"),
+    ("🏁", "Flag"),
+    ("🚂", "Train"),
+    ("🚲", "Bicycle"),
+    ("✈️", "Aeroplane"),
+    ("🚀", "Rocket"),
+    ("🏆", "Trophy"),
+    ("⚽", "Ball"),
+    ("🎸", "Guitar"),
+    ("🎺", "Trumpet"),
+    ("🔔", "Bell"),
+    ("⚓", "Anchor"),
+    ("🎧", "Headphones"),
+    ("📁", "Folder"),
+    ("📌", "Pin"),
+];
+
+/// Returns the emoji and their names for the first 42 bits of the fingerprint.
+pub fn emoji(fingerprint: &Fingerprint) -> Vec<(&'static str, &'static str)> {
+    let bits = fingerprint[..EMOJI_BYTES]
+        .iter()
+        .fold(0u64, |bits, &byte| bits << 8 | u64::from(byte));
+    (0..EMOJI_COUNT)
+        .map(|idx| EMOJI[(bits >> (48 - 6 * (idx + 1)) & 0x3f) as usize])
+        .collect()
+}
+
+/// Formats the bytes following the emoji, e.g. `3FA2 91C4`, so that comparing
+/// both checks 74 bits.
+pub fn short_hex(fingerprint: &Fingerprint) -> String {
+    fingerprint[EMOJI_BYTES..EMOJI_BYTES + SHORT_HEX_SIZE]
+        .chunks(2)
+        .map(|pair| format!("{:02X}{:02X}", pair[0], pair[1]))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The form shown to humans, the emoji followed by the short hex form.
+pub fn short(fingerprint: &Fingerprint) -> String {
+    let emoji: Vec<&str> = emoji(fingerprint)
+        .into_iter()
+        .map(|(emoji, _)| emoji)
+        .collect();
+    format!("{} ({})", emoji.join(" "), short_hex(fingerprint))
+}
+
+/// The short form with the names of the emoji, for dialogs where the emoji may
+/// be rendered too small to tell them apart.
+pub fn short_with_names(fingerprint: &Fingerprint) -> String {
+    let emoji: Vec<String> = emoji(fingerprint)
+        .into_iter()
+        .map(|(emoji, name)| format!("{} {}", emoji, name))
+        .collect();
+    format!("{} ({})", emoji.join(", "), short_hex(fingerprint))
+}
@@ -1,16 +1,141 @@
+//! Bindings of the bundled html5-qrcode library. Only the config and camera
+//! types are used with the `rust-decoder` feature.
+
+#[cfg(not(feature = "rust-decoder"))]
+use js_sys::{Array, Object, Promise, Reflect};
+#[cfg(not(feature = "rust-decoder"))]
 use wasm_bindgen::prelude::*;
+#[cfg(not(feature = "rust-decoder"))]
+use wasm_bindgen_futures::JsFuture;
+
+/// Scanning options of `Html5Qrcode::start`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Html5QrcodeConfig {
+    /// Frames per second that are passed to the decoder.
+    pub fps: u32,
+    /// Edge length of the square scanning region in pixels.
+    pub qrbox: Option<u32>,
+    /// Requested aspect ratio of the video feed.
+    pub aspect_ratio: Option<f64>,
+    /// Don't retry with a mirrored image when decoding fails.
+    pub disable_flip: bool,
+}
+
+impl Default for Html5QrcodeConfig {
+    fn default() -> Self {
+        Self {
+            fps: 10,
+            qrbox: Some(250),
+            aspect_ratio: None,
+            disable_flip: false,
+        }
+    }
+}
 
+#[cfg(not(feature = "rust-decoder"))]
+impl Html5QrcodeConfig {
+    /// Builds the config object, leaving out unset options.
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        let config = Object::new();
+        Reflect::set(&config, &"fps".into(), &self.fps.into())?;
+        if let Some(qrbox) = self.qrbox {
+            Reflect::set(&config, &"qrbox".into(), &qrbox.into())?;
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            Reflect::set(&config, &"aspectRatio".into(), &aspect_ratio.into())?;
+        }
+        if self.disable_flip {
+            Reflect::set(&config, &"disableFlip".into(), &JsValue::TRUE)?;
+        }
+        Ok(config.into())
+    }
+}
+
+/// A video input as reported by `Html5Qrcode.getCameras()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraDevice {
+    pub id: String,
+    pub label: String,
+}
+
+#[cfg(not(feature = "rust-decoder"))]
 #[wasm_bindgen]
 extern "C" {
-    pub type Html5QrcodeScanner;
+    /// The low-level scanner without any UI besides the video feed.
+    #[derive(Clone)]
+    pub type Html5Qrcode;
 
     #[wasm_bindgen(constructor)]
-    pub fn new(id: &str, verbose: bool) -> Html5QrcodeScanner;
+    pub fn new(id: &str, verbose: bool) -> Html5Qrcode;
+
+    /// Resolves to an array of `{ id, label }` objects.
+    #[wasm_bindgen(static_method_of = Html5Qrcode, js_name = getCameras)]
+    pub fn get_cameras() -> Promise;
+
+    #[wasm_bindgen(method, catch)]
+    pub fn start(
+        this: &Html5Qrcode,
+        camera_id: &str,
+        config: &JsValue,
+        on_scan_success: &Closure<dyn FnMut(String)>,
+        on_scan_failure: &Closure<dyn FnMut(JsValue)>,
+    ) -> Result<Promise, JsValue>;
 
     #[wasm_bindgen(method)]
-    pub fn render(
-        this: &Html5QrcodeScanner,
+    pub fn stop(this: &Html5Qrcode) -> Promise;
+
+    /// Removes the video feed. Throws while a scan is running.
+    #[wasm_bindgen(method, catch)]
+    pub fn clear(this: &Html5Qrcode) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, getter, js_name = _isScanning)]
+    pub fn is_scanning(this: &Html5Qrcode) -> bool;
+}
+
+#[cfg(not(feature = "rust-decoder"))]
+pub async fn get_cameras() -> Result<Vec<CameraDevice>, JsValue> {
+    let cameras: Array = JsFuture::from(Html5Qrcode::get_cameras())
+        .await?
+        .dyn_into()?;
+    cameras
+        .iter()
+        .map(|camera| {
+            let field = |name: &str| -> Result<String, JsValue> {
+                Ok(Reflect::get(&camera, &JsValue::from_str(name))?
+                    .as_string()
+                    .unwrap_or_default())
+            };
+            Ok(CameraDevice {
+                id: field("id")?,
+                label: field("label")?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "rust-decoder"))]
+impl Html5Qrcode {
+    pub async fn start_camera(
+        &self,
+        camera_id: &str,
+        config: &Html5QrcodeConfig,
         on_scan_success: &Closure<dyn FnMut(String)>,
         on_scan_failure: &Closure<dyn FnMut(JsValue)>,
-    );
+    ) -> Result<(), JsValue> {
+        let started = self.start(
+            camera_id,
+            &config.to_js()?,
+            on_scan_success,
+            on_scan_failure,
+        )?;
+        JsFuture::from(started).await.map(|_| ())
+    }
+
+    /// Stops a running scan (if any) and removes the video feed.
+    pub async fn shutdown(&self) -> Result<(), JsValue> {
+        if self.is_scanning() {
+            JsFuture::from(self.stop()).await?;
+        }
+        self.clear()
+    }
 }
@@ -0,0 +1,167 @@
+//! JavaScript API for using the signing and verification in other web apps,
+//! without mounting the Yew UI.
+//!
+//! All functions taking or returning payloads use the complete contents of the
+//! QR code, e.g. `SIGN:` followed by the signature and the data.
+
+use crate::{
+    crypto::{self, subtle},
+    error::Error,
+    payload::Payload,
+    qr_generator::{self, Encoding},
+};
+use js_sys::{Object, Reflect, Uint8Array};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::CryptoKey;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &str = r#"
+export interface VerifyResult {
+  /** Whether the signature matches the public key. */
+  valid: boolean;
+  /** The signed data. */
+  data: Uint8Array;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Promise<KeyPair>")]
+    pub type KeyPairPromise;
+    #[wasm_bindgen(typescript_type = "Promise<Uint8Array>")]
+    pub type BytesPromise;
+    #[wasm_bindgen(typescript_type = "Promise<VerifyResult>")]
+    pub type VerifyResultPromise;
+    #[wasm_bindgen(typescript_type = "Promise<string>")]
+    pub type StringPromise;
+}
+
+/// An ECDSA P-256 key pair.
+#[wasm_bindgen]
+pub struct KeyPair {
+    public_key: CryptoKey,
+    private_key: CryptoKey,
+}
+
+#[wasm_bindgen]
+impl KeyPair {
+    #[wasm_bindgen(getter = publicKey)]
+    pub fn public_key(&self) -> CryptoKey {
+        self.public_key.clone()
+    }
+
+    #[wasm_bindgen(getter = privateKey)]
+    pub fn private_key(&self) -> CryptoKey {
+        self.private_key.clone()
+    }
+
+    /// Serializes the key pair in the key file format read by `importKeyFile`.
+    #[wasm_bindgen(js_name = exportKeyFile)]
+    pub fn export_key_file(&self) -> StringPromise {
+        let public_key = self.public_key.clone();
+        let private_key = self.private_key.clone();
+        future_to_promise(async move {
+            let json = crypto::export_key_pair_file(&subtle()?, &public_key, &private_key).await?;
+            Ok(JsValue::from_str(&json))
+        })
+        .unchecked_into()
+    }
+}
+
+/// Generates a new key pair.
+#[wasm_bindgen(js_name = generateKeyPair)]
+pub fn generate_key_pair() -> KeyPairPromise {
+    future_to_promise(async move {
+        let (public_key, private_key) = crypto::generate_keypair(&subtle()?).await?;
+        Ok(KeyPair {
+            public_key,
+            private_key,
+        }
+        .into())
+    })
+    .unchecked_into()
+}
+
+/// Reads a key file as exported by the app or `KeyPair.exportKeyFile`.
+#[wasm_bindgen(js_name = importKeyFile)]
+pub fn import_key_file(contents: String) -> KeyPairPromise {
+    future_to_promise(async move {
+        let (public_key, private_key) = crypto::import_key_pair_file(&subtle()?, &contents).await?;
+        Ok(KeyPair {
+            public_key,
+            private_key,
+        }
+        .into())
+    })
+    .unchecked_into()
+}
+
+/// Signs `text` and returns the payload for the QR code.
+///
+/// The signature is stored DER encoded if `der` is set, which makes the code a
+/// few bytes larger.
+#[wasm_bindgen(js_name = signText)]
+pub fn sign_text(private_key: &CryptoKey, text: String, der: Option<bool>) -> BytesPromise {
+    let private_key = private_key.clone();
+    future_to_promise(async move {
+        let signature = crypto::sign(&subtle()?, &private_key, text.as_bytes()).await?;
+        let mut signature = Uint8Array::new(&signature).to_vec();
+        if der.unwrap_or(false) {
+            signature = crypto::raw_to_der_signature(&signature)?;
+        }
+        let payload = Payload::signed(signature, text.into_bytes())?;
+        Ok(Uint8Array::from(payload.to_bytes().as_slice()).into())
+    })
+    .unchecked_into()
+}
+
+/// Packs a signature made elsewhere, raw or DER encoded, with the signed data
+/// into a payload for the QR code.
+#[wasm_bindgen(js_name = packSignature)]
+pub fn pack_signature(signature: Vec<u8>, data: Vec<u8>) -> Result<Vec<u8>, JsValue> {
+    Ok(Payload::signed(signature, data)?.to_bytes())
+}
+
+/// Checks the signature of a scanned payload.
+///
+/// Rejects if the payload isn't a signed code.
+#[wasm_bindgen(js_name = verifyPayload)]
+pub fn verify_payload(public_key: &CryptoKey, payload: Vec<u8>) -> VerifyResultPromise {
+    let public_key = public_key.clone();
+    future_to_promise(async move {
+        let payload = Payload::parse(&payload)?;
+        let data = match &payload {
+            Payload::Signed { data, .. }
+            | Payload::DerSigned { data, .. }
+            | Payload::WebAuthn { data, .. } => data,
+            _ => return Err(Error::malformed("This code is not signed.").into()),
+        };
+        payload.check_assertion()?;
+        let signature = payload.raw_signature()?.unwrap_or_default();
+        let message = payload.signed_message().unwrap_or_default();
+        let valid = crypto::verify(&subtle()?, &public_key, &signature, &message).await?;
+
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("valid"), &JsValue::from(valid))?;
+        Reflect::set(
+            &result,
+            &JsValue::from_str("data"),
+            &Uint8Array::from(data.as_slice()),
+        )?;
+        Ok(result.into())
+    })
+    .unchecked_into()
+}
+
+/// Renders a payload as QR code in SVG format, as Base45 text in alphanumeric
+/// mode if `base45` is set. Both are read by `verifyPayload`.
+#[wasm_bindgen(js_name = encodeQrSvg)]
+pub fn encode_qr_svg(payload: &[u8], base45: Option<bool>) -> Result<String, JsValue> {
+    let encoding = if base45.unwrap_or(false) {
+        Encoding::Base45
+    } else {
+        Encoding::Binary
+    };
+    Ok(qr_generator::encode_data_as(payload, &encoding)?)
+}
@@ -0,0 +1,172 @@
+//! Detection of key file formats and PEM encoding.
+//!
+//! Supported are the JSON key pair files written by the app, single JWKs,
+//! PKCS#8 private keys and SubjectPublicKeyInfo public keys as PEM or DER, and
+//! raw public keys as found in `PUB:` codes, also base64 encoded.
+
+use crate::error::Error;
+
+pub const PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+pub const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+
+/// Size of an uncompressed P-256 point.
+const RAW_PUBLIC_KEY_SIZE: usize = 65;
+/// Size of a compressed P-256 point.
+const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
+/// First byte of an uncompressed point.
+const UNCOMPRESSED_POINT: u8 = 0x04;
+/// First bytes of a compressed point with even and odd `y`.
+const COMPRESSED_POINT: [u8; 2] = [0x02, 0x03];
+/// Tag of an ASN.1 SEQUENCE.
+const SEQUENCE: u8 = 0x30;
+/// Tag of an ASN.1 INTEGER, the version that starts every PKCS#8 structure.
+const INTEGER: u8 = 0x02;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyFile {
+    /// The JSON key pair file of the app or a JWK.
+    Json(String),
+    /// DER encoded keys. At least one of them is present.
+    Der {
+        private_key: Option<Vec<u8>>,
+        public_key: Option<Vec<u8>>,
+    },
+    /// A compressed or uncompressed point.
+    RawPublicKey(Vec<u8>),
+}
+
+impl KeyFile {
+    /// Detects the format of a key file and decodes it if needed.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let text = std::str::from_utf8(bytes).ok().map(str::trim_start);
+        match text {
+            Some(text) if text.starts_with('{') => Ok(Self::Json(text.to_owned())),
+            Some(text) if text.contains("-----BEGIN ") => Self::from_pem(text),
+            _ => Self::from_binary(bytes).or_else(|err| {
+                // Keys pasted as text are usually base64 encoded.
+                match text.and_then(|text| base64::decode(text.trim()).ok()) {
+                    Some(decoded) => Self::from_binary(&decoded),
+                    None => Err(err),
+                }
+            }),
+        }
+    }
+
+    fn from_binary(bytes: &[u8]) -> Result<Self, Error> {
+        let raw = match bytes.len() {
+            RAW_PUBLIC_KEY_SIZE => bytes[0] == UNCOMPRESSED_POINT,
+            COMPRESSED_PUBLIC_KEY_SIZE => COMPRESSED_POINT.contains(&bytes[0]),
+            _ => false,
+        };
+        if raw {
+            Ok(Self::RawPublicKey(bytes.to_vec()))
+        } else {
+            Self::from_der(bytes)
+        }
+    }
+
+    fn from_pem(text: &str) -> Result<Self, Error> {
+        let mut private_key = None;
+        let mut public_key = None;
+        for (label, der) in pem_decode(text)? {
+            match label.as_str() {
+                PRIVATE_KEY_LABEL => private_key = Some(der),
+                PUBLIC_KEY_LABEL => public_key = Some(der),
+                "EC PRIVATE KEY" => {
+                    return Err(Error::UnsupportedAlgorithm(
+                        "SEC1 keys are not supported, convert them to PKCS#8 with \
+                         `openssl pkcs8 -topk8 -nocrypt`."
+                            .to_owned(),
+                    ))
+                }
+                "ENCRYPTED PRIVATE KEY" => {
+                    return Err(Error::UnsupportedAlgorithm(
+                        "Encrypted private keys are not supported.".to_owned(),
+                    ))
+                }
+                _ => {}
+            }
+        }
+        if private_key.is_none() && public_key.is_none() {
+            return Err(Error::malformed("The file contains no key."));
+        }
+        Ok(Self::Der {
+            private_key,
+            public_key,
+        })
+    }
+
+    fn from_der(der: &[u8]) -> Result<Self, Error> {
+        // PKCS#8 starts with the version, SubjectPublicKeyInfo with the algorithm.
+        match der_sequence_contents(der)?.first() {
+            Some(&INTEGER) => Ok(Self::Der {
+                private_key: Some(der.to_vec()),
+                public_key: None,
+            }),
+            Some(&SEQUENCE) => Ok(Self::Der {
+                private_key: None,
+                public_key: Some(der.to_vec()),
+            }),
+            _ => Err(Error::malformed("This file is not a key.")),
+        }
+    }
+}
+
+/// Returns the contents of the SEQUENCE that makes up `der`.
+fn der_sequence_contents(der: &[u8]) -> Result<&[u8], Error> {
+    let not_a_key = || Error::malformed("This file is not a key.");
+    if der.first() != Some(&SEQUENCE) {
+        return Err(not_a_key());
+    }
+    let (length, header) = match *der.get(1).ok_or_else(not_a_key)? {
+        length if length < 0x80 => (length as usize, 2),
+        0x81 => (*der.get(2).ok_or_else(not_a_key)? as usize, 3),
+        0x82 => {
+            let length = der.get(2..4).ok_or_else(not_a_key)?;
+            ((length[0] as usize) << 8 | length[1] as usize, 4)
+        }
+        _ => return Err(not_a_key()),
+    };
+    der.get(header..header + length).ok_or_else(not_a_key)
+}
+
+/// Encodes DER data as PEM block with the given label.
+pub fn pem_encode(label: &str, der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Decodes all PEM blocks in `text`, returned as label and DER data.
+pub fn pem_decode(text: &str) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("-----BEGIN ") {
+        let after_begin = &rest[start + "-----BEGIN ".len()..];
+        let label_end = after_begin
+            .find("-----")
+            .ok_or_else(|| Error::malformed("Unterminated PEM header."))?;
+        let label = &after_begin[..label_end];
+        let body = &after_begin[label_end + "-----".len()..];
+
+        let end_marker = format!("-----END {}-----", label);
+        let body_end = body
+            .find(&end_marker)
+            .ok_or_else(|| Error::malformed(format!("Missing end of {}.", label)))?;
+        let base64: String = body[..body_end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let der = base64::decode(&base64)
+            .map_err(|_| Error::malformed(format!("Invalid base64 in {}.", label)))?;
+        blocks.push((label.to_owned(), der));
+
+        rest = &body[body_end + end_marker.len()..];
+    }
+    Ok(blocks)
+}
@@ -1,31 +1,98 @@
-#![recursion_limit = "512"]
+#![recursion_limit = "1024"]
 
-use wasm_bindgen::prelude::*;
-use yew::prelude::*;
+//! Signing and verification of QR codes with ECDSA P-256.
+//!
+//! The core of the crate is usable from any Rust program:
+//!
+//! * [`payload`] parses and builds the contents of the QR codes,
+//! * [`metadata`] adds the issue time, station and batch to signed data,
+//! * [`ecdsa`] generates keys, signs and verifies in pure Rust,
+//! * [`crypto`] does the same through WebCrypto when running in a browser,
+//! * [`qr_generator`] renders payloads as SVG, binary, as [`base45`] text or
+//!   as [`payload_url`] opening the verifier,
+//! * [`webauthn`] checks signatures made by security keys,
+//! * [`key_file`] reads and writes keys as PEM and DER,
+//! * [`fingerprint`] identifies public keys for humans,
+//! * [`js_api`] exports the above to JavaScript.
+//!
+//! The Yew app is built on top of these with the default `web` feature. Disable
+//! default features to use only the library, e.g. in backend services.
 
+pub mod base45;
+pub mod crypto;
+#[cfg(feature = "rust-decoder")]
+pub mod decoder;
+pub mod ecdsa;
+pub mod error;
+pub mod fingerprint;
+pub mod js_api;
+pub mod key_file;
+pub mod metadata;
+pub mod payload;
+pub mod payload_url;
+pub mod qr_generator;
+pub mod webauthn;
+
+#[cfg(feature = "web")]
 mod app;
-mod crypto;
+#[cfg(feature = "web")]
+mod batch;
+#[cfg(feature = "web")]
+mod camera;
+#[cfg(feature = "web")]
+mod feedback;
+#[cfg(feature = "web")]
+mod files;
+#[cfg(feature = "web")]
 mod html5_qrcode;
-mod qr_generator;
+#[cfg(feature = "web")]
 mod qr_reader;
+#[cfg(feature = "web")]
+mod quick_sign;
+#[cfg(feature = "web")]
+mod scanner;
+#[cfg(feature = "web")]
+mod security_key;
+#[cfg(feature = "web")]
+mod verification_log;
+#[cfg(feature = "web")]
+mod verify_element;
+#[cfg(feature = "web")]
+mod worker;
 
-#[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+pub use error::Error;
+pub use payload::Payload;
 
-#[wasm_bindgen(start)]
-pub fn main_js() -> Result<(), JsValue> {
-    #[cfg(debug_assertions)]
-    console_error_panic_hook::set_once();
+#[cfg(feature = "web")]
+pub use web::*;
 
-    App::<app::Main>::new().mount_to_body();
+#[cfg(feature = "web")]
+mod web {
+    use crate::{app, worker};
+    use wasm_bindgen::prelude::*;
+    use yew::prelude::*;
 
-    Ok(())
-}
+    #[global_allocator]
+    static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+
+    #[wasm_bindgen]
+    pub fn main_js() -> Result<(), JsValue> {
+        #[cfg(debug_assertions)]
+        console_error_panic_hook::set_once();
+
+        App::<app::Main>::new().mount_to_body();
+
+        Ok(())
+    }
+
+    /// Entry point of the batch signing web worker.
+    #[wasm_bindgen]
+    pub fn worker_main() -> Result<(), JsValue> {
+        #[cfg(debug_assertions)]
+        console_error_panic_hook::set_once();
+
+        worker::run();
 
-fn subtle() -> web_sys::SubtleCrypto {
-    let window = web_sys::window().unwrap();
-    window
-        .crypto()
-        .expect("No WebCrypto support found!")
-        .subtle()
+        Ok(())
+    }
 }
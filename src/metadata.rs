@@ -0,0 +1,169 @@
+//! Issue time, signing station and batch, signed together with the data of a
+//! code.
+//!
+//! The metadata is put in front of the data, enclosed in record separators
+//! (`0x1E`) with the fields separated by unit separators (`0x1F`):
+//!
+//! ```text
+//! \x1Et=1714564800\x1Fs=Gate 3\x1Fb=May\x1ETicket #42
+//! ```
+//!
+//! Data without metadata is left as is, so older codes are still read.
+
+use crate::error::Error;
+use std::fmt;
+
+const RECORD_SEPARATOR: u8 = 0x1E;
+const UNIT_SEPARATOR: u8 = 0x1F;
+
+const ISSUED_AT: &str = "t";
+const STATION: &str = "s";
+const BATCH: &str = "b";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Seconds since the Unix epoch.
+    pub issued_at: Option<u64>,
+    pub station: Option<String>,
+    pub batch: Option<String>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        self.issued_at.is_none() && self.station.is_none() && self.batch.is_none()
+    }
+
+    /// Returns the metadata to sign now, with the current time as issue time if
+    /// `timestamp` is set.
+    pub fn issued_now(&self, timestamp: bool) -> Self {
+        Self {
+            issued_at: if timestamp { Some(unix_time()) } else { None },
+            ..self.clone()
+        }
+    }
+
+    /// Puts the metadata in front of `data`, which stays unchanged if there is
+    /// none.
+    pub fn wrap(&self, data: &[u8]) -> Vec<u8> {
+        if self.is_empty() {
+            return data.to_vec();
+        }
+        let mut fields = Vec::new();
+        if let Some(issued_at) = self.issued_at {
+            fields.push(format!("{}={}", ISSUED_AT, issued_at));
+        }
+        if let Some(station) = &self.station {
+            fields.push(format!("{}={}", STATION, strip_separators(station)));
+        }
+        if let Some(batch) = &self.batch {
+            fields.push(format!("{}={}", BATCH, strip_separators(batch)));
+        }
+        let fields = fields.join(&(UNIT_SEPARATOR as char).to_string());
+        [
+            &[RECORD_SEPARATOR],
+            fields.as_bytes(),
+            &[RECORD_SEPARATOR],
+            data,
+        ]
+        .concat()
+    }
+
+    /// Separates the metadata from signed data, returning empty metadata and
+    /// all of `data` if there is none.
+    pub fn split(data: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let rest = match data.split_first() {
+            Some((&RECORD_SEPARATOR, rest)) => rest,
+            _ => return Ok((Self::default(), data)),
+        };
+        let end = rest
+            .iter()
+            .position(|&byte| byte == RECORD_SEPARATOR)
+            .ok_or_else(|| Error::malformed("The metadata is not terminated."))?;
+        let fields = std::str::from_utf8(&rest[..end])
+            .map_err(|_| Error::malformed("The metadata is not valid UTF-8."))?;
+
+        let mut metadata = Self::default();
+        for field in fields.split(UNIT_SEPARATOR as char) {
+            let (name, value) = match field.find('=') {
+                Some(idx) => (&field[..idx], &field[idx + 1..]),
+                None => return Err(Error::malformed("Invalid metadata field.")),
+            };
+            match name {
+                ISSUED_AT => {
+                    metadata.issued_at = Some(
+                        value
+                            .parse()
+                            .map_err(|_| Error::malformed("Invalid issue time."))?,
+                    )
+                }
+                STATION => metadata.station = Some(value.to_owned()),
+                BATCH => metadata.batch = Some(value.to_owned()),
+                // Fields added later are skipped by older versions.
+                _ => {}
+            }
+        }
+        Ok((metadata, &rest[end + 1..]))
+    }
+}
+
+impl fmt::Display for Metadata {
+    /// One line per field, e.g. `Station: Gate 3`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        if let Some(issued_at) = self.issued_at {
+            lines.push(format!("Issued: {}", format_timestamp(issued_at)));
+        }
+        if let Some(station) = &self.station {
+            lines.push(format!("Station: {}", station));
+        }
+        if let Some(batch) = &self.batch {
+            lines.push(format!("Batch: {}", batch));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn strip_separators(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&c| c != RECORD_SEPARATOR as char && c != UNIT_SEPARATOR as char)
+        .collect()
+}
+
+/// Formats seconds since the Unix epoch as `2024-05-01 12:00:00 UTC`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Returns the current time as seconds since the Unix epoch, as told by the
+/// JavaScript environment.
+pub fn unix_time() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
@@ -0,0 +1,394 @@
+use crate::{
+    base45,
+    crypto::{self, SignatureEncoding},
+    error::Error,
+    fingerprint::Fingerprint,
+    payload_url, webauthn,
+};
+
+pub const PUBLIC_KEY_PREFIX: &[u8] = b"PUB:";
+pub const SIGNED_PREFIX: &[u8] = b"SIGN:";
+pub const DER_SIGNED_PREFIX: &[u8] = b"SIGD:";
+pub const FILE_PREFIX: &[u8] = b"FILE:";
+pub const ROTATION_PREFIX: &[u8] = b"ROT:";
+pub const MULTI_SIGNED_PREFIX: &[u8] = b"MULTI:";
+pub const WEBAUTHN_PREFIX: &[u8] = b"WAUTH:";
+
+/// The prefixes of all codes.
+pub const PREFIXES: [&[u8]; 7] = [
+    PUBLIC_KEY_PREFIX,
+    SIGNED_PREFIX,
+    DER_SIGNED_PREFIX,
+    FILE_PREFIX,
+    ROTATION_PREFIX,
+    MULTI_SIGNED_PREFIX,
+    WEBAUTHN_PREFIX,
+];
+
+/// Size of a raw (IEEE P1363) ECDSA P-256 signature.
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// Size of a SHA-256 hash.
+pub const HASH_SIZE: usize = 32;
+
+/// Size of the key ids in multi-signature codes.
+pub const KEY_ID_SIZE: usize = 4;
+
+/// Identifies the key of a co-signature, see [`key_id`].
+pub type KeyId = [u8; KEY_ID_SIZE];
+
+/// The contents of a QR code produced by this app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    /// `PUB:` followed by the public key as compressed point, or uncompressed
+    /// in codes of older versions.
+    PublicKey(Vec<u8>),
+    /// `SIGN:` followed by the signature and the signed data.
+    Signed { signature: Vec<u8>, data: Vec<u8> },
+    /// `SIGD:` followed by a DER encoded signature and the signed data, for
+    /// signatures made outside of the app. The DER structure includes its
+    /// length, which tells where the data starts.
+    DerSigned { signature: Vec<u8>, data: Vec<u8> },
+    /// `FILE:` followed by the signature, the SHA-256 hash of a file and its
+    /// name. The file itself is checked separately.
+    File {
+        signature: Vec<u8>,
+        hash: [u8; HASH_SIZE],
+        name: String,
+    },
+    /// `ROT:` followed by the signatures of the old and the new key, the length
+    /// of the old key and both raw keys. Announces that `new_key` replaces
+    /// `old_key`, both signatures cover [`rotation_message`].
+    Rotation {
+        old_key: Vec<u8>,
+        new_key: Vec<u8>,
+        old_signature: Vec<u8>,
+        new_signature: Vec<u8>,
+    },
+    /// `MULTI:` followed by the number of signatures, each preceded by the id of
+    /// its key, and the data signed by all of them, see
+    /// [`multi_signed_message`].
+    MultiSigned {
+        signatures: Vec<Cosignature>,
+        data: Vec<u8>,
+    },
+    /// `WAUTH:` followed by the length of the DER encoded signature (1 byte),
+    /// the signature, the length of the authenticator data (2 bytes, big
+    /// endian), the authenticator data, the length of the client data (2 bytes)
+    /// and the client data, and finally the signed data. See [`webauthn`].
+    WebAuthn {
+        signature: Vec<u8>,
+        authenticator_data: Vec<u8>,
+        client_data: Vec<u8>,
+        data: Vec<u8>,
+    },
+}
+
+/// One of the signatures of a multi-signature code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cosignature {
+    pub key_id: KeyId,
+    pub signature: Vec<u8>,
+}
+
+impl Payload {
+    /// Parses the contents of a QR code, which are either the payload itself,
+    /// its Base45 encoding or a URL with the Base45 encoding as fragment.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        // Binary payloads practically never consist of Base45 characters only.
+        let decoded = std::str::from_utf8(bytes).ok().and_then(|text| {
+            payload_url::from_url(text)
+                .unwrap_or_else(|| base45::decode(text))
+                .ok()
+        });
+        match decoded.map(|decoded| Self::parse_binary(&decoded)) {
+            Some(Ok(payload)) => Ok(payload),
+            _ => Self::parse_binary(bytes),
+        }
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<Self, Error> {
+        if let Some(key) = bytes.strip_prefix(PUBLIC_KEY_PREFIX) {
+            if key.is_empty() {
+                return Err(Error::malformed("The public key is missing."));
+            }
+            Ok(Self::PublicKey(key.to_vec()))
+        } else if let Some(rest) = bytes.strip_prefix(SIGNED_PREFIX) {
+            if rest.len() < SIGNATURE_SIZE {
+                return Err(Error::malformed("The signature is truncated."));
+            }
+            let (signature, data) = rest.split_at(SIGNATURE_SIZE);
+            Ok(Self::Signed {
+                signature: signature.to_vec(),
+                data: data.to_vec(),
+            })
+        } else if let Some(rest) = bytes.strip_prefix(DER_SIGNED_PREFIX) {
+            let size = match rest {
+                // A SEQUENCE, which for P-256 signatures always has a short length.
+                [0x30, length, ..] if *length < 0x80 => 2 + *length as usize,
+                _ => return Err(Error::malformed("The signature is not DER encoded.")),
+            };
+            if rest.len() < size {
+                return Err(Error::malformed("The signature is truncated."));
+            }
+            let (signature, data) = rest.split_at(size);
+            crypto::der_to_raw_signature(signature)?;
+            Ok(Self::DerSigned {
+                signature: signature.to_vec(),
+                data: data.to_vec(),
+            })
+        } else if let Some(rest) = bytes.strip_prefix(FILE_PREFIX) {
+            if rest.len() < SIGNATURE_SIZE + HASH_SIZE {
+                return Err(Error::malformed("The file signature is truncated."));
+            }
+            let (signature, rest) = rest.split_at(SIGNATURE_SIZE);
+            let (hash, name) = rest.split_at(HASH_SIZE);
+            let mut file_hash = [0; HASH_SIZE];
+            file_hash.copy_from_slice(hash);
+            Ok(Self::File {
+                signature: signature.to_vec(),
+                hash: file_hash,
+                name: String::from_utf8(name.to_vec())
+                    .map_err(|_| Error::malformed("The file name is not valid UTF-8."))?,
+            })
+        } else if let Some(rest) = bytes.strip_prefix(ROTATION_PREFIX) {
+            let truncated = || Error::malformed("The key rotation is truncated.");
+            if rest.len() < 2 * SIGNATURE_SIZE + 1 {
+                return Err(truncated());
+            }
+            let (old_signature, rest) = rest.split_at(SIGNATURE_SIZE);
+            let (new_signature, rest) = rest.split_at(SIGNATURE_SIZE);
+            let (old_key_size, keys) = (rest[0] as usize, &rest[1..]);
+            if old_key_size == 0 || keys.len() <= old_key_size {
+                return Err(truncated());
+            }
+            let (old_key, new_key) = keys.split_at(old_key_size);
+            Ok(Self::Rotation {
+                old_key: old_key.to_vec(),
+                new_key: new_key.to_vec(),
+                old_signature: old_signature.to_vec(),
+                new_signature: new_signature.to_vec(),
+            })
+        } else if let Some(rest) = bytes.strip_prefix(MULTI_SIGNED_PREFIX) {
+            let (&count, mut rest) = rest
+                .split_first()
+                .ok_or_else(|| Error::malformed("The number of signatures is missing."))?;
+            let mut signatures = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                if rest.len() < KEY_ID_SIZE + SIGNATURE_SIZE {
+                    return Err(Error::malformed("The signatures are truncated."));
+                }
+                let (key_id, signature) = rest.split_at(KEY_ID_SIZE);
+                let (signature, remaining) = signature.split_at(SIGNATURE_SIZE);
+                let mut cosignature = Cosignature {
+                    key_id: [0; KEY_ID_SIZE],
+                    signature: signature.to_vec(),
+                };
+                cosignature.key_id.copy_from_slice(key_id);
+                signatures.push(cosignature);
+                rest = remaining;
+            }
+            Ok(Self::MultiSigned {
+                signatures,
+                data: rest.to_vec(),
+            })
+        } else if let Some(rest) = bytes.strip_prefix(WEBAUTHN_PREFIX) {
+            let truncated = || Error::malformed("The security key assertion is truncated.");
+            let (&signature_size, rest) = rest.split_first().ok_or_else(truncated)?;
+            let signature = rest.get(..signature_size as usize).ok_or_else(truncated)?;
+            let rest = &rest[signature.len()..];
+            let (authenticator_data, rest) = split_with_length(rest).ok_or_else(truncated)?;
+            let (client_data, data) = split_with_length(rest).ok_or_else(truncated)?;
+            webauthn::check(authenticator_data, client_data, data)?;
+            Ok(Self::WebAuthn {
+                signature: signature.to_vec(),
+                authenticator_data: authenticator_data.to_vec(),
+                client_data: client_data.to_vec(),
+                data: data.to_vec(),
+            })
+        } else {
+            Err(Error::malformed("This is not a code created by this app."))
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::PublicKey(key) => [PUBLIC_KEY_PREFIX, key].concat(),
+            Self::Signed { signature, data } => [SIGNED_PREFIX, signature, data].concat(),
+            Self::DerSigned { signature, data } => [DER_SIGNED_PREFIX, signature, data].concat(),
+            Self::File {
+                signature,
+                hash,
+                name,
+            } => [FILE_PREFIX, signature, hash, name.as_bytes()].concat(),
+            Self::Rotation {
+                old_key,
+                new_key,
+                old_signature,
+                new_signature,
+            } => [
+                ROTATION_PREFIX,
+                old_signature,
+                new_signature,
+                &[old_key.len() as u8],
+                old_key,
+                new_key,
+            ]
+            .concat(),
+            Self::MultiSigned { signatures, data } => {
+                let mut bytes = [MULTI_SIGNED_PREFIX, &[signatures.len() as u8]].concat();
+                for cosignature in signatures {
+                    bytes.extend_from_slice(&cosignature.key_id);
+                    bytes.extend_from_slice(&cosignature.signature);
+                }
+                bytes.extend_from_slice(data);
+                bytes
+            }
+            Self::WebAuthn {
+                signature,
+                authenticator_data,
+                client_data,
+                data,
+            } => [
+                WEBAUTHN_PREFIX,
+                &[signature.len() as u8],
+                signature,
+                &(authenticator_data.len() as u16).to_be_bytes(),
+                authenticator_data,
+                &(client_data.len() as u16).to_be_bytes(),
+                client_data,
+                data,
+            ]
+            .concat(),
+        }
+    }
+
+    /// Packs a signature in either encoding with its data, as `SIGN:` code if
+    /// it is raw and as `SIGD:` code if it is DER encoded.
+    pub fn signed(signature: Vec<u8>, data: Vec<u8>) -> Result<Self, Error> {
+        match SignatureEncoding::detect(&signature) {
+            Some(SignatureEncoding::Raw) => Ok(Self::Signed { signature, data }),
+            Some(SignatureEncoding::Der) => Ok(Self::DerSigned { signature, data }),
+            None => Err(Error::malformed("Invalid signature.")),
+        }
+    }
+
+    /// The signature of codes signed by a single key, raw or DER encoded.
+    pub fn signature(&self) -> Option<&[u8]> {
+        match self {
+            Self::PublicKey(_) | Self::Rotation { .. } | Self::MultiSigned { .. } => None,
+            Self::Signed { signature, .. }
+            | Self::DerSigned { signature, .. }
+            | Self::File { signature, .. }
+            | Self::WebAuthn { signature, .. } => Some(signature),
+        }
+    }
+
+    /// How [`signature`](Self::signature) is encoded, as told by the prefix.
+    pub fn signature_encoding(&self) -> Option<SignatureEncoding> {
+        match self {
+            Self::PublicKey(_) | Self::Rotation { .. } | Self::MultiSigned { .. } => None,
+            Self::Signed { .. } | Self::File { .. } => Some(SignatureEncoding::Raw),
+            Self::DerSigned { .. } | Self::WebAuthn { .. } => Some(SignatureEncoding::Der),
+        }
+    }
+
+    /// [`signature`](Self::signature) converted to the raw format, as checked
+    /// by [`crypto::verify`].
+    pub fn raw_signature(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.signature()
+            .zip(self.signature_encoding())
+            .map(|(signature, encoding)| encoding.to_raw(signature))
+            .transpose()
+    }
+
+    /// Checks that a security key assertion was made for its data with the
+    /// user present, see [`webauthn::check`]. Other codes always pass.
+    ///
+    /// The signature only covers the hash of the data, so this has to be
+    /// checked along with it.
+    pub fn check_assertion(&self) -> Result<(), Error> {
+        match self {
+            Self::WebAuthn {
+                authenticator_data,
+                client_data,
+                data,
+                ..
+            } => webauthn::check(authenticator_data, client_data, data),
+            _ => Ok(()),
+        }
+    }
+
+    /// The bytes covered by [`signature`](Self::signature).
+    pub fn signed_message(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::PublicKey(_) | Self::Rotation { .. } | Self::MultiSigned { .. } => None,
+            Self::Signed { data, .. } | Self::DerSigned { data, .. } => Some(data.clone()),
+            Self::File { hash, name, .. } => Some(file_message(hash, name)),
+            Self::WebAuthn {
+                authenticator_data,
+                client_data,
+                ..
+            } => Some(webauthn::signed_message(authenticator_data, client_data)),
+        }
+    }
+
+    /// Adds a signature to a multi-signature code, replacing an earlier one of
+    /// the same key.
+    pub fn add_signature(&mut self, cosignature: Cosignature) -> Result<(), Error> {
+        let signatures = match self {
+            Self::MultiSigned { signatures, .. } => signatures,
+            _ => return Err(Error::malformed("This code can't be co-signed.")),
+        };
+        signatures.retain(|existing| existing.key_id != cosignature.key_id);
+        if signatures.len() >= u8::MAX as usize {
+            return Err(Error::malformed("The code has too many signatures."));
+        }
+        signatures.push(cosignature);
+        Ok(())
+    }
+}
+
+/// Splits a field preceded by its length as 2 bytes big endian off `bytes`.
+fn split_with_length(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let length = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize;
+    let field = bytes.get(2..2 + length)?;
+    Some((field, &bytes[2 + length..]))
+}
+
+/// Returns the id of a key in multi-signature codes, which are the first bytes
+/// of its fingerprint.
+pub fn key_id(fingerprint: &Fingerprint) -> KeyId {
+    let mut key_id = [0; KEY_ID_SIZE];
+    key_id.copy_from_slice(&fingerprint[..KEY_ID_SIZE]);
+    key_id
+}
+
+/// Returns the message signed for a file, which covers its name as well.
+pub fn file_message(hash: &[u8; HASH_SIZE], name: &str) -> Vec<u8> {
+    [hash, name.as_bytes()].concat()
+}
+
+/// Returns the message signed by every key of a multi-signature code.
+///
+/// It starts with the prefix, so that a single signature of a code that is
+/// still collecting signatures can't be rewrapped as a `SIGN:` code over the
+/// same data, which would get around the threshold.
+pub fn multi_signed_message(data: &[u8]) -> Vec<u8> {
+    [MULTI_SIGNED_PREFIX, data].concat()
+}
+
+/// Returns the message signed by both keys of a rotation.
+///
+/// `SIGN:` codes sign their data as it is, so the old key's signature with this
+/// message as data is also a valid `SIGN:` code. Such a code verifies as signed
+/// text, starting with `ROT:` and followed by both keys in binary, and states
+/// no more than the rotation itself: that the old key endorsed the new one.
+pub fn rotation_message(old_key: &[u8], new_key: &[u8]) -> Vec<u8> {
+    [ROTATION_PREFIX, &[old_key.len() as u8], old_key, new_key].concat()
+}
+
+/// Returns the SHA-256 hash of the contents of a file.
+pub fn file_hash(contents: &[u8]) -> [u8; HASH_SIZE] {
+    hmac_sha256::Hash::hash(contents)
+}
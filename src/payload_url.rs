@@ -0,0 +1,69 @@
+//! Payloads in the fragment of a URL, e.g. `https://verify.example/#<base45>`.
+//!
+//! Phone cameras open such codes in the browser, where the app reads the
+//! payload from `location.hash` and verifies it. The fragment is never sent to
+//! the server. The payload is Base45 encoded, with the two characters that
+//! aren't allowed as they are in URLs escaped.
+
+use crate::{base45, error::Error, payload};
+
+/// Returns `base_url` with `payload` as fragment, replacing any fragment it
+/// already has.
+pub fn to_url(base_url: &str, payload: &[u8]) -> String {
+    let base_url = base_url.split('#').next().unwrap_or_default();
+    format!("{}#{}", base_url, encode_fragment(payload))
+}
+
+/// Whether a fragment holds a payload rather than e.g. the id of an element.
+///
+/// Only the start of the payload is checked, so that broken payloads are
+/// still reported.
+pub fn is_payload_fragment(fragment: &str) -> bool {
+    let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+    // Base45 encodes pairs of bytes, all prefixes are at least two pairs long.
+    payload::PREFIXES
+        .iter()
+        .any(|prefix| fragment.starts_with(&encode_fragment(&prefix[..4])))
+}
+
+fn encode_fragment(payload: &[u8]) -> String {
+    let mut fragment = String::new();
+    for c in base45::encode(payload).chars() {
+        match c {
+            ' ' => fragment.push_str("%20"),
+            '%' => fragment.push_str("%25"),
+            c => fragment.push(c),
+        }
+    }
+    fragment
+}
+
+/// Decodes the payload from a URL, `None` if it has no fragment.
+pub fn from_url(url: &str) -> Option<Result<Vec<u8>, Error>> {
+    url.find('#').map(|idx| decode_fragment(&url[idx + 1..]))
+}
+
+/// Decodes the payload from a fragment as returned by `location.hash`, with or
+/// without the leading `#`.
+pub fn decode_fragment(fragment: &str) -> Result<Vec<u8>, Error> {
+    let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+    let mut text = Vec::with_capacity(fragment.len());
+    let mut bytes = fragment.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let escaped = [bytes.next(), bytes.next()];
+            let value = match escaped {
+                [Some(high), Some(low)] => std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                _ => None,
+            };
+            text.push(value.ok_or_else(|| Error::malformed("Invalid escape in the URL."))?);
+        } else {
+            text.push(byte);
+        }
+    }
+    let text =
+        String::from_utf8(text).map_err(|_| Error::malformed("The URL is not valid UTF-8."))?;
+    base45::decode(&text)
+}
@@ -1,7 +1,68 @@
+use crate::{base45, error::Error, payload_url};
 use qrcodegen::{QrCode, QrCodeEcc};
 
-pub fn encode_data(data: &[u8]) -> Result<String, ()> {
-    QrCode::encode_binary(data, QrCodeEcc::Low)
-        .map_err(|_| ())
-        .map(|qr| qr.to_svg_string(5))
+/// Quiet zone around the code in modules.
+pub const BORDER: i32 = 5;
+
+/// How payloads are stored in QR codes. Scanned codes are read in any
+/// encoding by [`Payload::parse`](crate::payload::Payload::parse).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// The bytes as they are, in byte mode.
+    #[default]
+    Binary,
+    /// Base45 text in alphanumeric mode, see [`base45`].
+    Base45,
+    /// A link to the verifier at the given URL, see [`payload_url`].
+    Url(String),
+}
+
+impl Encoding {
+    /// All encodings, linking to the verifier at `base_url`.
+    pub fn all(base_url: &str) -> [Encoding; 3] {
+        [
+            Encoding::Binary,
+            Encoding::Base45,
+            Encoding::Url(base_url.to_owned()),
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binary => "Binary",
+            Self::Base45 => "Base45",
+            Self::Url(_) => "URL",
+        }
+    }
+
+    pub fn from_name(name: &str, base_url: &str) -> Option<Self> {
+        Self::all(base_url)
+            .iter()
+            .find(|encoding| encoding.name() == name)
+            .cloned()
+    }
+}
+
+pub fn encode_qr(data: &[u8]) -> Result<QrCode, Error> {
+    encode_qr_as(data, &Encoding::Binary)
+}
+
+pub fn encode_qr_as(data: &[u8], encoding: &Encoding) -> Result<QrCode, Error> {
+    match encoding {
+        Encoding::Binary => QrCode::encode_binary(data, QrCodeEcc::Low),
+        // Only uses characters of the alphanumeric mode, which is picked for them.
+        Encoding::Base45 => QrCode::encode_text(&base45::encode(data), QrCodeEcc::Low),
+        Encoding::Url(base_url) => {
+            QrCode::encode_text(&payload_url::to_url(base_url, data), QrCodeEcc::Low)
+        }
+    }
+    .map_err(|_| Error::QrCapacityExceeded)
+}
+
+pub fn encode_data(data: &[u8]) -> Result<String, Error> {
+    encode_data_as(data, &Encoding::Binary)
+}
+
+pub fn encode_data_as(data: &[u8], encoding: &Encoding) -> Result<String, Error> {
+    encode_qr_as(data, encoding).map(|qr| qr.to_svg_string(BORDER))
 }
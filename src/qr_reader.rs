@@ -1,28 +1,186 @@
-use crate::{crypto, html5_qrcode::Html5QrcodeScanner, subtle};
+use crate::{
+    camera,
+    crypto::{self, subtle},
+    ecdsa,
+    error::Error,
+    feedback::Feedback,
+    files::{read_file, selected_file},
+    fingerprint::{self, Fingerprint},
+    html5_qrcode::{CameraDevice, Html5QrcodeConfig},
+    metadata::Metadata,
+    payload::{self, Cosignature, Payload},
+    scanner::Scanner,
+};
+use std::{collections::HashMap, rc::Rc, time::Duration};
 use uuid::Uuid;
-use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys::CryptoKey;
-use yew::prelude::*;
+use yew::{
+    prelude::*,
+    services::{timeout::TimeoutTask, TimeoutService},
+};
+
+/// How long the kiosk result screen stays visible before scanning resumes.
+const KIOSK_RESET: Duration = Duration::from_millis(2500);
 
 pub struct QrReader {
+    link: ComponentLink<Self>,
     qr_ref: NodeRef,
     reader_id: Uuid,
-    scanner: Option<Html5QrcodeScanner>,
-    scanned_closure: Closure<dyn FnMut(String)>,
-    error_closure: Closure<dyn FnMut(JsValue)>,
+    scanner: Option<Rc<Scanner>>,
+    config: Html5QrcodeConfig,
+    cameras: Vec<CameraDevice>,
+    camera: Option<String>,
+    preferred_camera: Option<String>,
+    active_camera: Option<String>,
+    state: ScanState,
+    user_paused: bool,
+    paused: bool,
+    torch: Option<bool>,
+    camera_error: Option<String>,
     onpublickey: Callback<CryptoKey>,
     public_key: Option<CryptoKey>,
-    last_message: Option<String>,
+    previous_keys: Vec<CryptoKey>,
+    onsuccessor: Callback<(CryptoKey, CryptoKey)>,
+    cosigner_keys: Vec<CryptoKey>,
+    threshold: usize,
+    oncosign: Option<Callback<Payload>>,
+    trusted_keys: Option<Vec<Fingerprint>>,
+    onverdict: Option<Callback<Verdict>>,
+    onresult: Callback<Verdict>,
+    kiosk: bool,
+    cooldown: u32,
+    recent: HashMap<Vec<u8>, f64>,
+    verdict: Option<Verdict>,
+    feedback: Feedback,
+    reset_task: Option<TimeoutTask>,
+    /// Hash, name and signer of a verified `FILE:` code waiting for the file.
+    pending_file: Option<([u8; 32], String, Signer)>,
+    file_input: NodeRef,
+    link_payload: Option<Vec<u8>>,
+    /// Whether `link_payload` still has to be verified.
+    link_payload_pending: bool,
 }
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct Props {
     pub onpublickey: Callback<CryptoKey>,
     pub public_key: Option<CryptoKey>,
+    /// Keys replaced by `public_key` through rotations, most recent first.
+    /// Codes signed by them are still valid.
+    #[prop_or_default]
+    pub previous_keys: Vec<CryptoKey>,
+    /// Receives the old and the new key of a valid `ROT:` code replacing
+    /// `public_key` or a trusted key. The old key should be passed on in
+    /// `previous_keys`.
+    #[prop_or_default]
+    pub onsuccessor: Callback<(CryptoKey, CryptoKey)>,
+    /// Further keys accepted for signatures, e.g. of other departments.
+    #[prop_or_default]
+    pub cosigner_keys: Vec<CryptoKey>,
+    /// Number of different known keys that have to sign a `MULTI:` code.
+    #[prop_or(1)]
+    pub threshold: usize,
+    /// Receives scanned `MULTI:` codes to add a signature, except in kiosk mode.
+    #[prop_or_default]
+    pub oncosign: Option<Callback<Payload>>,
+    /// Show results as a full-screen flash with sound instead of dialogs.
+    #[prop_or_default]
+    pub kiosk: bool,
+    /// Seconds during which the same payload is not processed again.
+    #[prop_or(5)]
+    pub cooldown: u32,
+    /// Applied whenever the camera is (re)started.
+    #[prop_or_default]
+    pub config: Html5QrcodeConfig,
+    /// Device id of the camera to use instead of the one remembered from the
+    /// last session.
+    #[prop_or_default]
+    pub camera: Option<String>,
+    /// Stops the camera until cleared, independent of the pause button.
+    #[prop_or_default]
+    pub paused: bool,
+    /// Fingerprints (SHA-256 of the raw public key) of keys that are imported
+    /// without asking, also in kiosk mode. All other keys are rejected if set.
+    #[prop_or_default]
+    pub trusted_keys: Option<Vec<Fingerprint>>,
+    /// Receives every result instead of it being shown in a dialog. Kiosks
+    /// still flash the result.
+    #[prop_or_default]
+    pub onverdict: Option<Callback<Verdict>>,
+    /// Receives every result in addition to it being shown, e.g. for a log.
+    #[prop_or_default]
+    pub onresult: Callback<Verdict>,
+    /// Payload of the URL the page was opened with, verified as soon as there
+    /// is a key to check it with.
+    #[prop_or_default]
+    pub link_payload: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ScanState {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+}
+
+#[derive(Clone, Debug)]
+pub enum Verdict {
+    Verified {
+        payload: String,
+        signers: Vec<Signer>,
+        metadata: Metadata,
+    },
+    Failed(String),
+}
+
+/// A key signatures are checked against.
+struct VerificationKey {
+    key: CryptoKey,
+    /// The key has been replaced by a rotation.
+    retired: bool,
+    /// Keys replaced by one another share this, so that one signer can't sign
+    /// a `MULTI:` code once with each of them.
+    chain: usize,
+}
+
+/// The key that made a valid signature.
+#[derive(Clone, Debug)]
+pub struct Signer {
+    pub fingerprint: Fingerprint,
+    /// The key has been replaced by a rotation since.
+    pub retired: bool,
+}
+
+impl Signer {
+    fn describe(&self) -> String {
+        let fingerprint = fingerprint::short_with_names(&self.fingerprint);
+        if self.retired {
+            format!("{} (previous key)", fingerprint)
+        } else {
+            fingerprint
+        }
+    }
 }
 
 pub enum Msg {
-    GotQRText(String),
+    GotQRData(Vec<u8>),
+    Verified(Verdict),
+    ResetKiosk,
+    GotCameras(Vec<CameraDevice>),
+    CameraError(String),
+    SelectCamera(String),
+    NextCamera,
+    Start,
+    Started,
+    Stopped,
+    TogglePause,
+    ToggleTorch,
+    TorchChanged(bool),
+    CheckFile([u8; 32], String, Signer),
+    FileSelected,
+    CancelFile,
 }
 
 impl Component for QrReader {
@@ -31,157 +189,843 @@ impl Component for QrReader {
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         Self {
+            link,
             qr_ref: NodeRef::default(),
             reader_id: Uuid::new_v4(),
             scanner: None,
-            scanned_closure: Closure::wrap(Box::new(move |text| {
-                link.send_message(Msg::GotQRText(text));
-            }) as Box<dyn FnMut(String)>),
-            error_closure: Closure::wrap(
-                Box::new(move |err: JsValue| web_sys::console::log_1(&err))
-                    as Box<dyn FnMut(JsValue)>,
-            ),
+            config: props.config,
+            cameras: Vec::new(),
+            camera: props.camera.clone().or_else(camera::remembered_camera),
+            preferred_camera: props.camera,
+            active_camera: None,
+            state: ScanState::Stopped,
+            user_paused: false,
+            paused: props.paused,
+            torch: None,
+            camera_error: None,
             onpublickey: props.onpublickey,
             public_key: props.public_key,
-            last_message: None,
+            previous_keys: props.previous_keys,
+            onsuccessor: props.onsuccessor,
+            cosigner_keys: props.cosigner_keys,
+            threshold: props.threshold,
+            oncosign: props.oncosign,
+            trusted_keys: props.trusted_keys,
+            onverdict: props.onverdict,
+            onresult: props.onresult,
+            kiosk: props.kiosk,
+            cooldown: props.cooldown,
+            recent: HashMap::new(),
+            verdict: None,
+            feedback: Feedback::default(),
+            reset_task: None,
+            pending_file: None,
+            file_input: NodeRef::default(),
+            link_payload: props.link_payload,
+            link_payload_pending: true,
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            Msg::GotQRText(text) => {
-                if self
-                    .last_message
-                    .as_deref()
-                    .map(|old_message| old_message != text)
-                    .unwrap_or(true)
-                {
-                    self.last_message = Some(text.clone());
-                    if text.len() > 5 {
-                        let binary: Vec<u8> = text.chars().map(|c| c as u8).collect();
-                        if &binary[0..4] == b"PUB:" {
-                            let onpublickey = self.onpublickey.clone();
-                            wasm_bindgen_futures::spawn_local(async move {
-                                let public_key = &binary[4..];
-                                let hash = hmac_sha256::Hash::hash(public_key);
-                                web_sys::console::log_2(
-                                    &wasm_bindgen::JsValue::from_str("Public Key Hash"),
-                                    &wasm_bindgen::JsValue::from_str(
-                                        &hash
-                                            .iter()
-                                            .map(|byte| format!("{:02X}", byte))
-                                            .collect::<Vec<String>>()
-                                            .join(":"),
-                                    ),
-                                );
-                                match crypto::import_public_key_raw(&subtle(), public_key).await {
-                                    Err(err) => {
-                                        web_sys::console::log_2(
-                                            &wasm_bindgen::JsValue::from_str("CRYPTO ERROR"),
-                                            &err,
-                                        );
-                                        web_sys::window()
-                                            .unwrap()
-                                            .alert_with_message(
-                                                &err.unchecked_into::<js_sys::Error>()
-                                                    .to_string()
-                                                    .as_string()
-                                                    .unwrap(),
-                                            )
-                                            .unwrap();
-                                    }
-                                    Ok(public_key) => {
-                                        let hash_text = hash
-                                            .iter()
-                                            .map(|byte| format!("{:02X}", byte))
-                                            .collect::<Vec<String>>()
-                                            .join(":");
-
-                                        if web_sys::window()
-                                            .unwrap()
-                                            .confirm_with_message(&format!(
-                                                "Import public key with hash {}",
-                                                hash_text
-                                            ))
-                                            .unwrap()
-                                        {
-                                            onpublickey.emit(public_key);
-                                        }
-                                    }
-                                }
-                            });
-                        } else if &binary[0..5] == b"SIGN:" {
-                            if let Some(public_key) = &self.public_key {
-                                let public_key = public_key.clone();
-                                let signature = binary[5..(5 + crypto::SIGNATURE_SIZE)].to_vec();
-                                let data = binary[(5 + crypto::SIGNATURE_SIZE)..].to_vec();
-                                wasm_bindgen_futures::spawn_local(async move {
-                                    match crypto::verify(&subtle(), &public_key, &signature, &data)
-                                        .await
-                                    {
-                                        Err(err) => {
-                                            web_sys::console::log_2(
-                                                &wasm_bindgen::JsValue::from_str("CRYPTO ERROR"),
-                                                &err,
-                                            );
-                                            web_sys::window()
-                                                .unwrap()
-                                                .alert_with_message(
-                                                    &err.unchecked_into::<js_sys::Error>()
-                                                        .to_string()
-                                                        .as_string()
-                                                        .unwrap(),
-                                                )
-                                                .unwrap();
-                                        }
-                                        Ok(flag) => {
-                                            if flag {
-                                                let payload = String::from_utf8(data)
-                                                    .unwrap_or_else(|_| "<binary data>".to_owned());
-                                                web_sys::window()
-                                                    .unwrap()
-                                                    .alert_with_message(&format!(
-                                                        "VERIFIED:\n{}",
-                                                        payload
-                                                    ))
-                                                    .unwrap();
-                                            } else {
-                                                web_sys::window()
-                                                    .unwrap()
-                                                    .alert_with_message("FAILED VERIFICATION!")
-                                                    .unwrap();
-                                            }
-                                        }
-                                    }
-                                });
+            Msg::GotQRData(data) => {
+                if self.verdict.is_none() && self.pending_file.is_none() && self.is_fresh(&data) {
+                    self.process(data);
+                }
+                false
+            }
+            Msg::Verified(verdict) => {
+                self.onresult.emit(verdict.clone());
+                if let Some(onverdict) = &self.onverdict {
+                    onverdict.emit(verdict.clone());
+                }
+                if self.kiosk {
+                    match &verdict {
+                        Verdict::Verified { .. } => self.feedback.success(),
+                        Verdict::Failed(_) => self.feedback.failure(),
+                    }
+                    self.verdict = Some(verdict);
+                    self.reset_task = Some(TimeoutService::spawn(
+                        KIOSK_RESET,
+                        self.link.callback(|_| Msg::ResetKiosk),
+                    ));
+                    true
+                } else if self.onverdict.is_none() {
+                    let message = match verdict {
+                        Verdict::Verified {
+                            payload,
+                            signers,
+                            metadata,
+                        } => {
+                            let mut message = format!("VERIFIED:\n{}\n\n", payload);
+                            if !metadata.is_empty() {
+                                message.push_str(&format!("{}\n", metadata));
                             }
+                            message.push_str("Signed by ");
+                            message.push_str(
+                                &signers
+                                    .iter()
+                                    .map(Signer::describe)
+                                    .collect::<Vec<_>>()
+                                    .join("\n"),
+                            );
+                            message
                         }
+                        Verdict::Failed(reason) => reason,
+                    };
+                    if let Some(window) = web_sys::window() {
+                        let _ = window.alert_with_message(&message);
                     }
+                    false
+                } else {
+                    false
                 }
             }
+            Msg::CheckFile(hash, name, signer) => {
+                self.pending_file = Some((hash, name, signer));
+                true
+            }
+            Msg::FileSelected => {
+                if let Some((hash, name, signer)) = self.pending_file.take() {
+                    self.check_file(hash, name, signer);
+                }
+                true
+            }
+            Msg::CancelFile => self.pending_file.take().is_some(),
+            Msg::ResetKiosk => {
+                self.reset_task = None;
+                self.verdict.take().is_some()
+            }
+            Msg::GotCameras(cameras) => {
+                let known = self
+                    .camera
+                    .as_ref()
+                    .map(|id| cameras.iter().any(|camera| &camera.id == id))
+                    .unwrap_or(false);
+                if !known {
+                    // Prefer the camera facing away from the user, if the labels tell.
+                    self.camera = cameras
+                        .iter()
+                        .find(|camera| {
+                            let label = camera.label.to_lowercase();
+                            label.contains("back") || label.contains("environment")
+                        })
+                        .or_else(|| cameras.first())
+                        .map(|camera| camera.id.clone());
+                }
+                self.cameras = cameras;
+                self.camera_error = None;
+                self.link.send_message(Msg::Start);
+                true
+            }
+            Msg::CameraError(message) => {
+                self.state = ScanState::Stopped;
+                self.camera_error = Some(message);
+                true
+            }
+            Msg::SelectCamera(id) => {
+                if self.camera.as_ref() == Some(&id) {
+                    return false;
+                }
+                camera::remember_camera(&id);
+                self.camera = Some(id);
+                self.restart();
+                true
+            }
+            Msg::NextCamera => {
+                let current = self
+                    .cameras
+                    .iter()
+                    .position(|camera| Some(&camera.id) == self.camera.as_ref());
+                let next = current
+                    .map(|idx| (idx + 1) % self.cameras.len())
+                    .unwrap_or(0);
+                if let Some(camera) = self.cameras.get(next) {
+                    let id = camera.id.clone();
+                    self.link.send_message(Msg::SelectCamera(id));
+                }
+                false
+            }
+            Msg::Start => {
+                if self.state != ScanState::Stopped || self.is_paused() {
+                    return false;
+                }
+                self.start();
+                true
+            }
+            Msg::Started => {
+                self.state = ScanState::Running;
+                self.torch = self
+                    .qr_ref
+                    .cast::<web_sys::Element>()
+                    .and_then(|element| camera::video_track(&element))
+                    .filter(camera::supports_torch)
+                    .map(|_| false);
+                if self.is_paused() || self.active_camera != self.camera {
+                    self.stop();
+                }
+                true
+            }
+            Msg::Stopped => {
+                self.state = ScanState::Stopped;
+                self.torch = None;
+                // The camera might have been changed or unpaused while stopping.
+                self.link.send_message(Msg::Start);
+                true
+            }
+            Msg::TogglePause => {
+                self.user_paused = !self.user_paused;
+                if self.is_paused() {
+                    self.stop();
+                } else {
+                    self.link.send_message(Msg::Start);
+                }
+                true
+            }
+            Msg::ToggleTorch => {
+                let on = match self.torch {
+                    Some(on) => !on,
+                    None => return false,
+                };
+                if let Some(track) = self
+                    .qr_ref
+                    .cast::<web_sys::Element>()
+                    .and_then(|element| camera::video_track(&element))
+                {
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match camera::set_torch(&track, on).await {
+                            Ok(()) => link.send_message(Msg::TorchChanged(on)),
+                            Err(err) => web_sys::console::log_2(
+                                &wasm_bindgen::JsValue::from_str("Failed switching torch"),
+                                &err,
+                            ),
+                        }
+                    });
+                }
+                false
+            }
+            Msg::TorchChanged(on) => {
+                self.torch = Some(on);
+                true
+            }
         }
-        false
     }
 
     fn rendered(&mut self, first_render: bool) {
         if first_render {
-            let scanner = Html5QrcodeScanner::new(&format!("{}", self.reader_id), true);
-            scanner.render(&self.scanned_closure, &self.error_closure);
+            self.verify_link_payload();
+            self.scanner = Some(Rc::new(Scanner::new(
+                &format!("{}", self.reader_id),
+                self.link.callback(Msg::GotQRData),
+            )));
 
-            self.scanner = Some(scanner);
+            let link = self.link.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match Scanner::cameras().await {
+                    Ok(cameras) if cameras.is_empty() => {
+                        link.send_message(Msg::CameraError("No camera found.".to_owned()))
+                    }
+                    Ok(cameras) => link.send_message(Msg::GotCameras(cameras)),
+                    Err(err) => link.send_message(Msg::CameraError(error_text(&err))),
+                }
+            });
+        }
+    }
+
+    fn destroy(&mut self) {
+        if let Some(scanner) = self.scanner.take() {
+            self.state = ScanState::Stopping;
+            // The scanner may still call back until the camera is released.
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = scanner.stop().await {
+                    web_sys::console::log_2(
+                        &wasm_bindgen::JsValue::from_str("Failed stopping scanner"),
+                        &err,
+                    );
+                }
+            });
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
         self.onpublickey = props.onpublickey;
         self.public_key = props.public_key;
+        self.previous_keys = props.previous_keys;
+        self.onsuccessor = props.onsuccessor;
+        self.cosigner_keys = props.cosigner_keys;
+        self.threshold = props.threshold;
+        self.oncosign = props.oncosign;
+        self.trusted_keys = props.trusted_keys;
+        self.onverdict = props.onverdict;
+        self.onresult = props.onresult;
+        self.cooldown = props.cooldown;
+        self.config = props.config;
+        if self.preferred_camera != props.camera {
+            self.preferred_camera = props.camera;
+            if let Some(camera) = self.preferred_camera.clone() {
+                self.link.send_message(Msg::SelectCamera(camera));
+            }
+        }
+        if self.paused != props.paused {
+            self.paused = props.paused;
+            if self.is_paused() {
+                self.stop();
+            } else {
+                self.link.send_message(Msg::Start);
+            }
+        }
+        if self.link_payload != props.link_payload {
+            self.link_payload = props.link_payload;
+            self.link_payload_pending = true;
+        }
+        self.verify_link_payload();
+        if self.kiosk != props.kiosk {
+            self.kiosk = props.kiosk;
+            self.verdict = None;
+            self.reset_task = None;
+            return true;
+        }
         false
     }
 
     fn view(&self) -> Html {
         html! {
-            <div class="reader" id={ format!("{}", self.reader_id) } ref=self.qr_ref.clone()>
+            <>
+                { self.view_controls() }
+                <div class="reader" id={ format!("{}", self.reader_id) } ref=self.qr_ref.clone()>
+                </div>
+                { self.view_file_check() }
+                { self.view_verdict() }
+            </>
+        }
+    }
+}
+
+impl QrReader {
+    /// Verifies the payload of the URL once, when the first key is known.
+    fn verify_link_payload(&mut self) {
+        if !self.link_payload_pending
+            || (self.public_key.is_none() && self.cosigner_keys.is_empty())
+        {
+            return;
+        }
+        self.link_payload_pending = false;
+        if let Some(payload) = self.link_payload.clone() {
+            self.process(payload);
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused || self.user_paused
+    }
+
+    fn start(&mut self) {
+        let (scanner, camera) = match (&self.scanner, &self.camera) {
+            (Some(scanner), Some(camera)) => (scanner.clone(), camera.clone()),
+            _ => return,
+        };
+        self.state = ScanState::Starting;
+        self.active_camera = Some(camera.clone());
+        let config = self.config.clone();
+        let link = self.link.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match scanner.start(&camera, &config).await {
+                Ok(()) => link.send_message(Msg::Started),
+                Err(err) => link.send_message(Msg::CameraError(error_text(&err))),
+            }
+        });
+    }
+
+    fn stop(&mut self) {
+        if self.state != ScanState::Running {
+            return;
+        }
+        if let Some(scanner) = self.scanner.clone() {
+            self.state = ScanState::Stopping;
+            let link = self.link.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = scanner.stop().await {
+                    web_sys::console::log_2(
+                        &wasm_bindgen::JsValue::from_str("Failed stopping scanner"),
+                        &err,
+                    );
+                }
+                link.send_message(Msg::Stopped);
+            });
+        }
+    }
+
+    fn restart(&mut self) {
+        match self.state {
+            ScanState::Running => self.stop(),
+            ScanState::Stopped => self.link.send_message(Msg::Start),
+            // `Msg::Started` and `Msg::Stopped` pick up the new camera.
+            ScanState::Starting | ScanState::Stopping => {}
+        }
+    }
+
+    fn view_controls(&self) -> Html {
+        let paused = self.is_paused();
+        html! {
+            <div class="reader_controls">
+                <select disabled={ self.cameras.len() < 2 } onchange=self.link.batch_callback(|data: ChangeData| match data {
+                    ChangeData::Select(select) => vec![Msg::SelectCamera(select.value())],
+                    _ => vec![],
+                })>
+                    { for self.cameras.iter().map(|camera| html! {
+                        <option value={ camera.id.clone() } selected={ Some(&camera.id) == self.camera.as_ref() }>
+                            { if camera.label.is_empty() { &camera.id } else { &camera.label } }
+                        </option>
+                    }) }
+                </select>
+                <button onclick=self.link.callback(|_| Msg::NextCamera) class="mdi-set mdi-camera-flip-outline" title="Switch Camera" disabled={ self.cameras.len() < 2 }></button>
+                <button onclick=self.link.callback(|_| Msg::ToggleTorch) class=("mdi-set", if self.torch == Some(true) { "mdi-flashlight-off" } else { "mdi-flashlight" }) title="Toggle Torch" disabled={ self.torch.is_none() }></button>
+                <button onclick=self.link.callback(|_| Msg::TogglePause) class=("mdi-set", if paused { "mdi-play" } else { "mdi-pause" }) title={ if paused { "Resume Scanning" } else { "Pause Scanning" } } disabled={ self.paused }></button>
+                {
+                    match &self.camera_error {
+                        Some(message) => html! { <span class="camera_error">{ message }</span> },
+                        None => html! {},
+                    }
+                }
             </div>
         }
     }
+
+    /// Returns whether the payload was not seen within the cooldown period, and
+    /// remembers it as seen now if so.
+    ///
+    /// Repeated scans don't extend the cooldown, so a code held in front of the
+    /// camera is checked again once it expired.
+    fn is_fresh(&mut self, data: &[u8]) -> bool {
+        let now = js_sys::Date::now();
+        let cooldown = f64::from(self.cooldown) * 1000.0;
+        self.recent.retain(|_, seen| now - *seen < cooldown);
+        if self.recent.contains_key(data) {
+            return false;
+        }
+        self.recent.insert(data.to_vec(), now);
+        true
+    }
+
+    fn process(&self, binary: Vec<u8>) {
+        let payload = match Payload::parse(&binary) {
+            Ok(payload) => payload,
+            Err(err) => {
+                self.reject(err.to_string());
+                return;
+            }
+        };
+        match payload {
+            Payload::PublicKey(public_key) => {
+                // Fingerprints are taken of the uncompressed point.
+                let public_key = match ecdsa::decompress_public_key(&public_key) {
+                    Ok(public_key) => public_key,
+                    Err(err) => {
+                        self.reject(err.to_string());
+                        return;
+                    }
+                };
+                let hash = fingerprint::fingerprint(&public_key);
+                if let Some(trusted_keys) = &self.trusted_keys {
+                    self.import_trusted(public_key, trusted_keys.contains(&hash));
+                    return;
+                }
+                // Unattended stations must not be reconfigured by whoever holds up a code.
+                if self.kiosk {
+                    return;
+                }
+                let onpublickey = self.onpublickey.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    web_sys::console::log_2(
+                        &wasm_bindgen::JsValue::from_str("Public Key Fingerprint"),
+                        &wasm_bindgen::JsValue::from_str(&fingerprint::to_hex(&hash)),
+                    );
+                    match async { crypto::import_public_key_raw(&subtle()?, &public_key).await }
+                        .await
+                    {
+                        Err(err) => err.report(),
+                        Ok(public_key) => {
+                            let confirmed = web_sys::window()
+                                .and_then(|window| {
+                                    window
+                                        .confirm_with_message(&format!(
+                                            "Import public key with fingerprint\n\n{}",
+                                            fingerprint::short_with_names(&hash)
+                                        ))
+                                        .ok()
+                                })
+                                .unwrap_or(false);
+                            if confirmed {
+                                onpublickey.emit(public_key);
+                            }
+                        }
+                    }
+                });
+            }
+            Payload::Rotation {
+                old_key,
+                new_key,
+                old_signature,
+                new_signature,
+            } => {
+                let link = self.link.clone();
+                let onsuccessor = self.onsuccessor.clone();
+                let public_key = self.public_key.clone();
+                let trusted = self
+                    .trusted_keys
+                    .as_ref()
+                    .map(|trusted_keys| trusted_keys.contains(&fingerprint::fingerprint(&old_key)))
+                    .unwrap_or(false);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = async {
+                        let subtle = subtle()?;
+                        let replaces_current = match &public_key {
+                            Some(public_key) => {
+                                crypto::fingerprint_of(&subtle, public_key).await?
+                                    == fingerprint::fingerprint(&old_key)
+                            }
+                            None => false,
+                        };
+                        if !trusted && !replaces_current {
+                            return Ok(Verdict::Failed(
+                                "KEY ROTATION FOR AN UNKNOWN KEY".to_owned(),
+                            ));
+                        }
+                        let successor = crypto::verify_rotation(
+                            &subtle,
+                            &old_key,
+                            &new_key,
+                            &old_signature,
+                            &new_signature,
+                        )
+                        .await?;
+                        Ok(match successor {
+                            Some(successor) => {
+                                let old_key =
+                                    crypto::import_public_key_raw(&subtle, &old_key).await?;
+                                onsuccessor.emit((old_key, successor));
+                                Verdict::Verified {
+                                    payload: "NEW KEY ADOPTED".to_owned(),
+                                    metadata: Metadata::default(),
+                                    signers: vec![Signer {
+                                        fingerprint: fingerprint::fingerprint(&new_key),
+                                        retired: false,
+                                    }],
+                                }
+                            }
+                            None => Verdict::Failed("FAILED VERIFICATION!".to_owned()),
+                        })
+                    }
+                    .await;
+                    link.send_message(Msg::Verified(
+                        result.unwrap_or_else(|err: Error| Verdict::Failed(err.to_string())),
+                    ));
+                });
+            }
+            Payload::MultiSigned { signatures, data } => {
+                let keys = match self.verification_keys(true) {
+                    Some(keys) => keys,
+                    None => return,
+                };
+                let link = self.link.clone();
+                let threshold = self.threshold;
+                let oncosign = self.oncosign.clone().filter(|_| !self.kiosk);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let verdict = match find_cosigners(&keys, &signatures, &data).await {
+                        Err(err) => Verdict::Failed(err.to_string()),
+                        Ok(signers) if signers.len() >= threshold => {
+                            let (payload, metadata) = signed_content(&data);
+                            Verdict::Verified {
+                                payload,
+                                signers,
+                                metadata,
+                            }
+                        }
+                        Ok(signers) => Verdict::Failed(format!(
+                            "ONLY {} OF {} REQUIRED SIGNATURES",
+                            signers.len(),
+                            threshold
+                        )),
+                    };
+                    link.send_message(Msg::Verified(verdict));
+                    if let Some(oncosign) = oncosign {
+                        oncosign.emit(Payload::MultiSigned { signatures, data });
+                    }
+                });
+            }
+            payload => {
+                let signature = payload
+                    .check_assertion()
+                    .and_then(|()| payload.raw_signature());
+                let (signature, message) = match (signature, payload.signed_message()) {
+                    (Ok(Some(signature)), Some(message)) => (signature, message),
+                    (Err(err), _) => {
+                        self.link
+                            .send_message(Msg::Verified(Verdict::Failed(err.to_string())));
+                        return;
+                    }
+                    _ => return,
+                };
+                // Co-signers only count towards the threshold of `MULTI:` codes,
+                // they can't issue codes on their own.
+                let keys = match self.verification_keys(false) {
+                    Some(keys) => keys,
+                    None => return,
+                };
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let verdict = match find_signer(&keys, &signature, &message).await {
+                        Err(err) => Verdict::Failed(err.to_string()),
+                        Ok(Some(signer)) => match payload {
+                            Payload::File { hash, name, .. } => {
+                                // Only half done, the file has to match as well.
+                                link.send_message(Msg::CheckFile(hash, name, signer));
+                                return;
+                            }
+                            Payload::WebAuthn { data, .. } => {
+                                let (payload, metadata) = signed_content(&data);
+                                Verdict::Verified {
+                                    payload,
+                                    signers: vec![signer],
+                                    metadata,
+                                }
+                            }
+                            _ => {
+                                let (payload, metadata) = signed_content(&message);
+                                Verdict::Verified {
+                                    payload,
+                                    signers: vec![signer],
+                                    metadata,
+                                }
+                            }
+                        },
+                        Ok(None) => Verdict::Failed("FAILED VERIFICATION!".to_owned()),
+                    };
+                    link.send_message(Msg::Verified(verdict));
+                });
+            }
+        }
+    }
+
+    /// Returns all keys signatures are checked against, including the keys of
+    /// co-signers if `cosigners` is set. Kiosks are told if there are none.
+    fn verification_keys(&self, cosigners: bool) -> Option<Vec<VerificationKey>> {
+        let own_keys = self
+            .public_key
+            .iter()
+            .map(|key| (key, false))
+            .chain(self.previous_keys.iter().map(|key| (key, true)))
+            .map(|(key, retired)| VerificationKey {
+                key: key.clone(),
+                retired,
+                chain: 0,
+            });
+        let cosigner_keys = self
+            .cosigner_keys
+            .iter()
+            .enumerate()
+            .filter(|_| cosigners)
+            .map(|(idx, key)| VerificationKey {
+                key: key.clone(),
+                retired: false,
+                chain: idx + 1,
+            });
+        let keys: Vec<_> = own_keys.chain(cosigner_keys).collect();
+        if keys.is_empty() {
+            self.reject("NO PUBLIC KEY LOADED".to_owned());
+            return None;
+        }
+        Some(keys)
+    }
+
+    /// Answers a scan that couldn't be verified. Kiosks and embedding pages
+    /// listening to `onverdict` get an answer to every scan, the app ignores
+    /// anything else in front of the camera.
+    fn reject(&self, reason: String) {
+        if self.kiosk || self.onverdict.is_some() {
+            self.link
+                .send_message(Msg::Verified(Verdict::Failed(reason)));
+        }
+    }
+
+    /// Compares the file chosen by the user with the hash of a verified `FILE:` code.
+    fn check_file(&self, hash: [u8; 32], name: String, signer: Signer) {
+        let file = match selected_file(&self.file_input) {
+            Some(file) => file,
+            None => return,
+        };
+        let link = self.link.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let verdict = match read_file(&file).await {
+                Ok(contents) if payload::file_hash(&contents) == hash => Verdict::Verified {
+                    payload: name,
+                    signers: vec![signer],
+                    metadata: Metadata::default(),
+                },
+                Ok(_) => Verdict::Failed("FILE DOES NOT MATCH THE SIGNATURE!".to_owned()),
+                Err(err) => Verdict::Failed(err.to_string()),
+            };
+            link.send_message(Msg::Verified(verdict));
+        });
+    }
+
+    fn view_file_check(&self) -> Html {
+        match &self.pending_file {
+            None => html! {},
+            Some((_, name, _)) => html! {
+                <div class="file_check">
+                    <label>
+                        { format!("The signature of \"{}\" is valid. Choose the file to check that it matches:", name) }
+                        <input type="file" ref=self.file_input.clone() onchange=self.link.callback(|_| Msg::FileSelected) />
+                    </label>
+                    <button onclick=self.link.callback(|_| Msg::CancelFile) class="mdi-set mdi-close" title="Cancel"></button>
+                </div>
+            },
+        }
+    }
+
+    fn import_trusted(&self, public_key: Vec<u8>, trusted: bool) {
+        let link = self.link.clone();
+        if !trusted {
+            link.send_message(Msg::Verified(Verdict::Failed(
+                "UNTRUSTED PUBLIC KEY".to_owned(),
+            )));
+            return;
+        }
+        let onpublickey = self.onpublickey.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match async { crypto::import_public_key_raw(&subtle()?, &public_key).await }.await {
+                Ok(public_key) => onpublickey.emit(public_key),
+                Err(err) => link.send_message(Msg::Verified(Verdict::Failed(err.to_string()))),
+            }
+        });
+    }
+
+    fn view_verdict(&self) -> Html {
+        match &self.verdict {
+            None => html! {},
+            Some(Verdict::Verified {
+                payload,
+                signers,
+                metadata,
+            }) => html! {
+                <div class="kiosk-flash success" onclick=self.link.callback(|_| Msg::ResetKiosk)>
+                    <span class="mdi mdi-check-circle-outline"></span>
+                    <div class="payload">{ payload }</div>
+                    {
+                        if metadata.is_empty() {
+                            html! {}
+                        } else {
+                            html! { <div class="metadata">{ metadata.to_string() }</div> }
+                        }
+                    }
+                    { for signers.iter().map(|signer| html! {
+                        <div class="signer">
+                            { "Signed by " }{ view_fingerprint(&signer.fingerprint) }
+                            { if signer.retired { " (previous key)" } else { "" } }
+                        </div>
+                    }) }
+                </div>
+            },
+            Some(Verdict::Failed(reason)) => html! {
+                <div class="kiosk-flash failure" onclick=self.link.callback(|_| Msg::ResetKiosk)>
+                    <span class="mdi mdi-close-circle-outline"></span>
+                    <div class="payload">{ reason }</div>
+                </div>
+            },
+        }
+    }
+}
+
+/// Returns the first of `keys` that made the signature.
+async fn find_signer(
+    keys: &[VerificationKey],
+    signature: &[u8],
+    message: &[u8],
+) -> Result<Option<Signer>, Error> {
+    let subtle = subtle()?;
+    for key in keys {
+        if crypto::verify(&subtle, &key.key, signature, message).await? {
+            return Ok(Some(Signer {
+                fingerprint: crypto::fingerprint_of(&subtle, &key.key).await?,
+                retired: key.retired,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns all of `keys` with a valid signature in a multi-signature code, each
+/// key and each rotation chain counted once.
+async fn find_cosigners(
+    keys: &[VerificationKey],
+    signatures: &[Cosignature],
+    data: &[u8],
+) -> Result<Vec<Signer>, Error> {
+    let subtle = subtle()?;
+    let message = payload::multi_signed_message(data);
+    let mut signers: Vec<Signer> = Vec::new();
+    let mut chains = Vec::new();
+    for key in keys {
+        if chains.contains(&key.chain) {
+            continue;
+        }
+        let fingerprint = crypto::fingerprint_of(&subtle, &key.key).await?;
+        if signers
+            .iter()
+            .any(|signer| signer.fingerprint == fingerprint)
+        {
+            continue;
+        }
+        let key_id = payload::key_id(&fingerprint);
+        for cosignature in signatures
+            .iter()
+            .filter(|cosignature| cosignature.key_id == key_id)
+        {
+            // A broken signature of one key doesn't spoil the others.
+            if crypto::verify(&subtle, &key.key, &cosignature.signature, &message)
+                .await
+                .unwrap_or(false)
+            {
+                signers.push(Signer {
+                    fingerprint,
+                    retired: key.retired,
+                });
+                chains.push(key.chain);
+                break;
+            }
+        }
+    }
+    Ok(signers)
+}
+
+/// Separates signed data into the text to show and its metadata. Data with
+/// broken metadata is shown as is.
+fn signed_content(data: &[u8]) -> (String, Metadata) {
+    let (metadata, data) = Metadata::split(data).unwrap_or_else(|_| (Metadata::default(), data));
+    let text = String::from_utf8(data.to_vec()).unwrap_or_else(|_| "<binary data>".to_owned());
+    (text, metadata)
+}
+
+/// Shows the short form of a fingerprint, with the names of the emoji and the
+/// complete fingerprint as tooltips.
+pub fn view_fingerprint(fingerprint: &Fingerprint) -> Html {
+    html! {
+        <span class="fingerprint" title=fingerprint::to_hex(fingerprint)>
+            { for fingerprint::emoji(fingerprint).into_iter().map(|(emoji, name)| html! {
+                <span class="emoji" title=name>{ emoji }</span>
+            }) }
+            <span class="short_hex">{ fingerprint::short_hex(fingerprint) }</span>
+        </span>
+    }
+}
+
+fn error_text(err: &JsValue) -> String {
+    err.as_string().unwrap_or_else(|| {
+        err.unchecked_ref::<js_sys::Error>()
+            .to_string()
+            .as_string()
+            .unwrap_or_default()
+    })
 }
@@ -0,0 +1,327 @@
+use crate::{
+    crypto::{subtle, Signer},
+    error::Error,
+    files::{download, download_url},
+    metadata::Metadata,
+    payload::Payload,
+    qr_generator::{encode_qr_as, Encoding, BORDER},
+};
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use qrcodegen::QrCode;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CanvasRenderingContext2d, CryptoKey, HtmlCanvasElement};
+use yew::{
+    prelude::*,
+    services::{timeout::TimeoutTask, TimeoutService},
+};
+
+/// Time without typing before the text is signed.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Pixels per module in PNG images.
+const PNG_SCALE: u32 = 8;
+
+/// Signs a single line of text as it is typed and previews the code.
+pub struct QuickSign {
+    link: ComponentLink<Self>,
+    private_key: CryptoKey,
+    metadata: Metadata,
+    timestamp: bool,
+    deterministic: bool,
+    encoding: Encoding,
+    text: String,
+    /// Incremented for every change, so that results for outdated text are dropped.
+    generation: u32,
+    debounce: Option<TimeoutTask>,
+    signed: Option<Signed>,
+    error: Option<String>,
+    copied: Option<bool>,
+    preview: NodeRef,
+}
+
+struct Signed {
+    qr: QrCode,
+    svg: String,
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    pub private_key: CryptoKey,
+    /// Signed along with the text.
+    #[prop_or_default]
+    pub metadata: Metadata,
+    /// Adds the time of signing to `metadata`.
+    #[prop_or_default]
+    pub timestamp: bool,
+    /// Signs as per RFC 6979, see [`Signer`].
+    #[prop_or_default]
+    pub deterministic: bool,
+    #[prop_or_default]
+    pub encoding: Encoding,
+}
+
+pub enum Msg {
+    Input(String),
+    Sign,
+    Signed(u32, Result<Vec<u8>, Error>),
+    DownloadSvg,
+    DownloadPng,
+    Copy,
+    Copied(bool),
+}
+
+impl Component for QuickSign {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            private_key: props.private_key,
+            metadata: props.metadata,
+            timestamp: props.timestamp,
+            deterministic: props.deterministic,
+            encoding: props.encoding,
+            text: String::new(),
+            generation: 0,
+            debounce: None,
+            signed: None,
+            error: None,
+            copied: None,
+            preview: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Input(text) => {
+                self.text = text;
+                self.generation += 1;
+                self.debounce = Some(TimeoutService::spawn(
+                    DEBOUNCE,
+                    self.link.callback(|_| Msg::Sign),
+                ));
+                false
+            }
+            Msg::Sign => {
+                self.debounce = None;
+                if self.text.is_empty() {
+                    self.set_signed(None);
+                    self.error = None;
+                    return true;
+                }
+                let generation = self.generation;
+                let private_key = self.private_key.clone();
+                let deterministic = self.deterministic;
+                let data = self
+                    .metadata
+                    .issued_now(self.timestamp)
+                    .wrap(self.text.as_bytes());
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = async {
+                        let subtle = subtle()?;
+                        let signer = Signer::new(&subtle, &private_key, deterministic).await?;
+                        let signature = signer.sign(&subtle, &data).await?;
+                        Ok(Payload::Signed { signature, data }.to_bytes())
+                    }
+                    .await;
+                    link.send_message(Msg::Signed(generation, result));
+                });
+                false
+            }
+            Msg::Signed(generation, result) => {
+                if generation != self.generation {
+                    return false;
+                }
+                match result.and_then(|payload| {
+                    let qr = encode_qr_as(&payload, &self.encoding)?;
+                    let svg = qr.to_svg_string(BORDER);
+                    Ok(Signed { qr, svg })
+                }) {
+                    Ok(signed) => {
+                        self.set_signed(Some(signed));
+                        self.error = None;
+                    }
+                    Err(err) => {
+                        self.set_signed(None);
+                        self.error = Some(err.to_string());
+                    }
+                }
+                true
+            }
+            Msg::DownloadSvg => {
+                if let Some(signed) = &self.signed {
+                    if let Err(err) = download(signed.svg.as_bytes(), "signed.svg") {
+                        err.report();
+                    }
+                }
+                false
+            }
+            Msg::DownloadPng => {
+                if let Some(signed) = &self.signed {
+                    let result = render_canvas(&signed.qr)
+                        .and_then(|canvas| Ok(canvas.to_data_url_with_type("image/png")?))
+                        .and_then(|url| download_url(&url, "signed.png"));
+                    if let Err(err) = result {
+                        err.report();
+                    }
+                }
+                false
+            }
+            Msg::Copy => {
+                if let Some(signed) = &self.signed {
+                    let qr = signed.qr.clone();
+                    let svg = signed.svg.clone();
+                    let link = self.link.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = copy_to_clipboard(&qr, &svg).await;
+                        if let Err(err) = &result {
+                            web_sys::console::log_2(&JsValue::from_str("Copying failed"), err);
+                        }
+                        link.send_message(Msg::Copied(result.is_ok()));
+                    });
+                }
+                false
+            }
+            Msg::Copied(copied) => {
+                self.copied = Some(copied);
+                true
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.private_key != props.private_key
+            || self.metadata != props.metadata
+            || self.timestamp != props.timestamp
+            || self.deterministic != props.deterministic
+            || self.encoding != props.encoding
+        {
+            self.private_key = props.private_key;
+            self.metadata = props.metadata;
+            self.timestamp = props.timestamp;
+            self.deterministic = props.deterministic;
+            self.encoding = props.encoding;
+            self.link.send_message(Msg::Input(self.text.clone()));
+        }
+        false
+    }
+
+    fn view(&self) -> Html {
+        let disabled = self.signed.is_none();
+        html! {
+            <div class="quick_sign">
+                <input type="text" placeholder="Text to sign" value=&self.text oninput=self.link.callback(|event: InputData| Msg::Input(event.value)) />
+                <div class="quick_qr" ref=self.preview.clone()></div>
+                {
+                    match &self.error {
+                        Some(error) => html! { <span class="quick_error">{ error }</span> },
+                        None => html! {},
+                    }
+                }
+                <div class="quick_actions">
+                    <button onclick=self.link.callback(|_| Msg::DownloadSvg) class="mdi-set mdi-svg" title="Download SVG" disabled=disabled></button>
+                    <button onclick=self.link.callback(|_| Msg::DownloadPng) class="mdi-set mdi-file-png-box" title="Download PNG" disabled=disabled></button>
+                    <button onclick=self.link.callback(|_| Msg::Copy) class="mdi-set mdi-content-copy" title="Copy to Clipboard" disabled=disabled></button>
+                    {
+                        match self.copied {
+                            Some(true) => html! { <span>{ "Copied!" }</span> },
+                            Some(false) => html! { <span class="quick_error">{ "Copying failed" }</span> },
+                            None => html! {},
+                        }
+                    }
+                </div>
+            </div>
+        }
+    }
+}
+
+impl QuickSign {
+    fn set_signed(&mut self, signed: Option<Signed>) {
+        if let Some(preview) = self.preview.cast::<web_sys::Element>() {
+            preview.set_inner_html(
+                signed
+                    .as_ref()
+                    .map(|signed| signed.svg.as_str())
+                    .unwrap_or(""),
+            );
+        }
+        self.signed = signed;
+        self.copied = None;
+    }
+}
+
+/// Draws `qr` onto a new canvas.
+fn render_canvas(qr: &QrCode) -> Result<HtmlCanvasElement, Error> {
+    let size = (qr.size() + 2 * BORDER) as u32 * PNG_SCALE;
+    let canvas: HtmlCanvasElement = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or(Error::Unavailable("document"))?
+        .create_element("canvas")?
+        .unchecked_into();
+    canvas.set_width(size);
+    canvas.set_height(size);
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or(Error::Unavailable("2D canvas"))?
+        .unchecked_into();
+
+    context.set_fill_style_str("#FFFFFF");
+    context.fill_rect(0.0, 0.0, f64::from(size), f64::from(size));
+    context.set_fill_style_str("#000000");
+    let scale = f64::from(PNG_SCALE);
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                context.fill_rect(
+                    f64::from(x + BORDER) * scale,
+                    f64::from(y + BORDER) * scale,
+                    scale,
+                    scale,
+                );
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+/// Copies the code as PNG image, or as SVG text where images can't be copied.
+///
+/// The Clipboard API is only available as unstable API in web-sys, so it is
+/// accessed dynamically.
+async fn copy_to_clipboard(qr: &QrCode, svg: &str) -> Result<(), JsValue> {
+    let navigator = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("No window available"))?
+        .navigator();
+    let clipboard = Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+    if clipboard.is_undefined() {
+        return Err(JsValue::from_str("Clipboard not available"));
+    }
+    let item_class = Reflect::get(&js_sys::global(), &JsValue::from_str("ClipboardItem"))?;
+
+    let promise = match item_class.dyn_ref::<Function>() {
+        Some(item_class) => {
+            let canvas = render_canvas(qr)?;
+            let blob = Promise::new(&mut |resolve, reject| {
+                if let Err(err) = canvas.to_blob(&resolve) {
+                    let _ = reject.call1(&JsValue::NULL, &err);
+                }
+            });
+            let items = Object::new();
+            Reflect::set(&items, &JsValue::from_str("image/png"), &blob)?;
+            let item = Reflect::construct(item_class, &Array::of1(&items))?;
+            call_method(&clipboard, "write", &Array::of1(&item))?
+        }
+        None => call_method(&clipboard, "writeText", &JsValue::from_str(svg))?,
+    };
+    JsFuture::from(promise.unchecked_into::<Promise>()).await?;
+    Ok(())
+}
+
+fn call_method(target: &JsValue, name: &str, arg: &JsValue) -> Result<JsValue, JsValue> {
+    Reflect::get(target, &JsValue::from_str(name))?
+        .unchecked_into::<Function>()
+        .call1(target, arg)
+}
@@ -0,0 +1,314 @@
+pub use backend::Scanner;
+
+#[cfg(not(feature = "rust-decoder"))]
+mod backend {
+    use crate::html5_qrcode::{self, CameraDevice, Html5Qrcode, Html5QrcodeConfig};
+    use wasm_bindgen::{closure::Closure, JsValue};
+    use yew::Callback;
+
+    /// Camera scanning through the bundled html5-qrcode library.
+    pub struct Scanner {
+        inner: Html5Qrcode,
+        scanned_closure: Closure<dyn FnMut(String)>,
+        error_closure: Closure<dyn FnMut(JsValue)>,
+    }
+
+    impl Scanner {
+        pub fn new(element_id: &str, ondata: Callback<Vec<u8>>) -> Self {
+            Self {
+                inner: Html5Qrcode::new(element_id, true),
+                scanned_closure: Closure::wrap(Box::new(move |text: String| {
+                    // Byte mode contents arrive as one character per byte.
+                    ondata.emit(text.chars().map(|c| c as u8).collect());
+                }) as Box<dyn FnMut(String)>),
+                error_closure: Closure::wrap(Box::new(move |err: JsValue| {
+                    web_sys::console::log_1(&err)
+                }) as Box<dyn FnMut(JsValue)>),
+            }
+        }
+
+        pub async fn cameras() -> Result<Vec<CameraDevice>, JsValue> {
+            html5_qrcode::get_cameras().await
+        }
+
+        pub async fn start(
+            &self,
+            camera_id: &str,
+            config: &Html5QrcodeConfig,
+        ) -> Result<(), JsValue> {
+            self.inner
+                .start_camera(
+                    camera_id,
+                    config,
+                    &self.scanned_closure,
+                    &self.error_closure,
+                )
+                .await
+        }
+
+        pub async fn stop(&self) -> Result<(), JsValue> {
+            self.inner.shutdown().await
+        }
+    }
+}
+
+#[cfg(feature = "rust-decoder")]
+mod backend {
+    use crate::{
+        decoder,
+        error::Error,
+        html5_qrcode::{CameraDevice, Html5QrcodeConfig},
+    };
+    use js_sys::{Object, Reflect};
+    use std::{cell::RefCell, time::Duration};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{
+        CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement, MediaDeviceInfo,
+        MediaDeviceKind, MediaDevices, MediaStream, MediaStreamConstraints, MediaStreamTrack,
+    };
+    use yew::{
+        services::{interval::IntervalTask, IntervalService},
+        Callback,
+    };
+
+    /// Frames are scaled down to this size along their longer edge before decoding.
+    const MAX_FRAME_SIZE: u32 = 800;
+
+    /// Camera scanning that grabs frames through a canvas and decodes them in wasm.
+    pub struct Scanner {
+        element_id: String,
+        ondata: Callback<Vec<u8>>,
+        running: RefCell<Option<Running>>,
+    }
+
+    struct Running {
+        stream: MediaStream,
+        video: HtmlVideoElement,
+        _task: IntervalTask,
+    }
+
+    /// The video showing the camera and the canvas its frames are drawn to.
+    struct Feed {
+        video: HtmlVideoElement,
+        canvas: HtmlCanvasElement,
+        context: CanvasRenderingContext2d,
+    }
+
+    impl Scanner {
+        pub fn new(element_id: &str, ondata: Callback<Vec<u8>>) -> Self {
+            Self {
+                element_id: element_id.to_owned(),
+                ondata,
+                running: RefCell::new(None),
+            }
+        }
+
+        pub async fn cameras() -> Result<Vec<CameraDevice>, JsValue> {
+            let media_devices = media_devices()?;
+            // Labels are only reported after the user granted camera access.
+            let constraints = MediaStreamConstraints::new();
+            constraints.set_video(&JsValue::TRUE);
+            let stream: MediaStream =
+                JsFuture::from(media_devices.get_user_media_with_constraints(&constraints)?)
+                    .await?
+                    .unchecked_into();
+            stop_tracks(&stream);
+
+            let devices: js_sys::Array = JsFuture::from(media_devices.enumerate_devices()?)
+                .await?
+                .unchecked_into();
+            Ok(devices
+                .iter()
+                .map(|device| device.unchecked_into::<MediaDeviceInfo>())
+                .filter(|device| device.kind() == MediaDeviceKind::Videoinput)
+                .map(|device| CameraDevice {
+                    id: device.device_id(),
+                    label: device.label(),
+                })
+                .collect())
+        }
+
+        pub async fn start(
+            &self,
+            camera_id: &str,
+            config: &Html5QrcodeConfig,
+        ) -> Result<(), JsValue> {
+            let device_id = Object::new();
+            Reflect::set(
+                &device_id,
+                &JsValue::from_str("exact"),
+                &JsValue::from_str(camera_id),
+            )?;
+            let video_constraints = Object::new();
+            Reflect::set(
+                &video_constraints,
+                &JsValue::from_str("deviceId"),
+                &device_id,
+            )?;
+            if let Some(aspect_ratio) = config.aspect_ratio {
+                Reflect::set(
+                    &video_constraints,
+                    &JsValue::from_str("aspectRatio"),
+                    &JsValue::from_f64(aspect_ratio),
+                )?;
+            }
+            let constraints = MediaStreamConstraints::new();
+            constraints.set_audio(&JsValue::FALSE);
+            constraints.set_video(&video_constraints);
+            let stream: MediaStream =
+                JsFuture::from(media_devices()?.get_user_media_with_constraints(&constraints)?)
+                    .await?
+                    .unchecked_into();
+
+            // The camera stays on until its tracks are stopped, so every failure
+            // from here on has to release it.
+            let Feed {
+                video,
+                canvas,
+                context,
+            } = match self.attach(&stream).await {
+                Ok(feed) => feed,
+                Err(err) => {
+                    stop_tracks(&stream);
+                    return Err(err);
+                }
+            };
+
+            let frame_video = video.clone();
+            let ondata = self.ondata.clone();
+            let (qrbox, disable_flip) = (config.qrbox, config.disable_flip);
+            let task = IntervalService::spawn(
+                Duration::from_millis(1000 / u64::from(config.fps.max(1))),
+                Callback::from(move |_| {
+                    for data in grab_frame(&frame_video, &canvas, &context, qrbox, disable_flip) {
+                        ondata.emit(data);
+                    }
+                }),
+            );
+
+            self.stop_running();
+            *self.running.borrow_mut() = Some(Running {
+                stream,
+                video,
+                _task: task,
+            });
+            Ok(())
+        }
+
+        /// Shows the stream in a video element and creates the canvas for it.
+        async fn attach(&self, stream: &MediaStream) -> Result<Feed, JsValue> {
+            let document = web_sys::window()
+                .and_then(|window| window.document())
+                .ok_or(Error::Unavailable("document"))?;
+            let container = document
+                .get_element_by_id(&self.element_id)
+                .ok_or_else(|| JsValue::from_str("Scanner element not found"))?;
+            let video: HtmlVideoElement = document.create_element("video")?.unchecked_into();
+            video.set_muted(true);
+            video.set_attribute("playsinline", "")?;
+            video.set_src_object(Some(stream));
+            container.append_child(&video)?;
+            if let Err(err) = JsFuture::from(video.play()?).await {
+                video.remove();
+                return Err(err);
+            }
+
+            let canvas: HtmlCanvasElement = document.create_element("canvas")?.unchecked_into();
+            let context = canvas
+                .get_context("2d")?
+                .ok_or_else(|| JsValue::from_str("No 2D canvas support"));
+            match context {
+                Ok(context) => Ok(Feed {
+                    video,
+                    canvas,
+                    context: context.unchecked_into(),
+                }),
+                Err(err) => {
+                    video.remove();
+                    Err(err)
+                }
+            }
+        }
+
+        pub async fn stop(&self) -> Result<(), JsValue> {
+            self.stop_running();
+            Ok(())
+        }
+
+        fn stop_running(&self) {
+            if let Some(running) = self.running.borrow_mut().take() {
+                stop_tracks(&running.stream);
+                running.video.remove();
+            }
+        }
+    }
+
+    fn media_devices() -> Result<MediaDevices, JsValue> {
+        web_sys::window()
+            .ok_or(Error::Unavailable("window"))?
+            .navigator()
+            .media_devices()
+    }
+
+    fn stop_tracks(stream: &MediaStream) {
+        for track in stream.get_tracks().iter() {
+            track.unchecked_into::<MediaStreamTrack>().stop();
+        }
+    }
+
+    fn grab_frame(
+        video: &HtmlVideoElement,
+        canvas: &HtmlCanvasElement,
+        context: &CanvasRenderingContext2d,
+        qrbox: Option<u32>,
+        disable_flip: bool,
+    ) -> Vec<Vec<u8>> {
+        let (video_width, video_height) = (video.video_width(), video.video_height());
+        if video_width == 0 || video_height == 0 {
+            return Vec::new();
+        }
+        // Like html5-qrcode, only the centered square of `qrbox` pixels is scanned.
+        let (source_width, source_height) = match qrbox {
+            Some(qrbox) => (qrbox.min(video_width), qrbox.min(video_height)),
+            None => (video_width, video_height),
+        };
+        let scale = f64::from(MAX_FRAME_SIZE) / f64::from(source_width.max(source_height));
+        let scale = scale.min(1.0);
+        let width = (f64::from(source_width) * scale) as u32;
+        let height = (f64::from(source_height) * scale) as u32;
+        canvas.set_width(width);
+        canvas.set_height(height);
+        if context
+            .draw_image_with_html_video_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                video,
+                f64::from(video_width - source_width) / 2.0,
+                f64::from(video_height - source_height) / 2.0,
+                f64::from(source_width),
+                f64::from(source_height),
+                0.0,
+                0.0,
+                f64::from(width),
+                f64::from(height),
+            )
+            .is_err()
+        {
+            return Vec::new();
+        }
+        let image_data = match context.get_image_data(0.0, 0.0, f64::from(width), f64::from(height))
+        {
+            Ok(image_data) => image_data,
+            Err(_) => return Vec::new(),
+        };
+        let mut luma = decoder::rgba_to_luma(&image_data.data());
+        let codes = decoder::decode_luma(width as usize, height as usize, &luma);
+        if !codes.is_empty() || disable_flip {
+            return codes;
+        }
+        // Codes seen through a mirror or from behind glass are flipped.
+        for row in luma.chunks_mut(width as usize) {
+            row.reverse();
+        }
+        decoder::decode_luma(width as usize, height as usize, &luma)
+    }
+}
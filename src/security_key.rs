@@ -0,0 +1,195 @@
+//! Signing keys on FIDO2 security keys, used through WebAuthn.
+//!
+//! The private key never leaves the authenticator, so there is nothing to
+//! export but the public key and the credential id. Assertions are packed into
+//! `WAUTH:` codes, see [`webauthn`](crate::webauthn).
+//!
+//! web-sys only has unstable bindings for WebAuthn, so the API is used through
+//! `Reflect`.
+
+use crate::{crypto, error::Error, payload::Payload};
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CryptoKey, SubtleCrypto};
+
+/// COSE algorithm identifier of ECDSA on P-256 with SHA-256.
+const ES256: i32 = -7;
+
+/// Name of the relying party shown by the browser when registering.
+const RP_NAME: &str = "QR Code Signer";
+
+/// Value of the `type` field identifying security key files.
+const SECURITY_KEY_FILE_TYPE: &str = "qr_security_key";
+
+/// A credential registered on a security key.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SecurityKey {
+    pub public_key: CryptoKey,
+    pub credential_id: Vec<u8>,
+}
+
+impl SecurityKey {
+    /// Creates a new credential, which asks the user to touch the security key.
+    pub async fn register(subtle: &SubtleCrypto, name: &str) -> Result<Self, Error> {
+        let user = object(&[
+            ("id", random_bytes(16)?.into()),
+            ("name", JsValue::from_str(name)),
+            ("displayName", JsValue::from_str(name)),
+        ])?;
+        let algorithm = object(&[
+            ("type", JsValue::from_str("public-key")),
+            ("alg", JsValue::from(ES256)),
+        ])?;
+        let options = object(&[
+            ("rp", object(&[("name", JsValue::from_str(RP_NAME))])?),
+            ("user", user),
+            ("challenge", random_bytes(32)?.into()),
+            ("pubKeyCredParams", Array::of1(&algorithm).into()),
+            ("attestation", JsValue::from_str("none")),
+        ])?;
+        let credential = call_credentials("create", &object(&[("publicKey", options)])?).await?;
+
+        let response = Reflect::get(&credential, &JsValue::from_str("response"))?;
+        let get_public_key = Reflect::get(&response, &JsValue::from_str("getPublicKey"))?;
+        let public_key = match get_public_key.dyn_ref::<Function>() {
+            Some(get_public_key) => get_public_key.call0(&response)?,
+            None => JsValue::NULL,
+        };
+        if public_key.is_null() || public_key.is_undefined() {
+            return Err(Error::UnsupportedAlgorithm(
+                "The browser doesn't expose the public key of the security key.".to_owned(),
+            ));
+        }
+        Ok(Self {
+            public_key: crypto::import_public_key_spki(subtle, &bytes(&public_key)).await?,
+            credential_id: bytes(&Reflect::get(&credential, &JsValue::from_str("rawId"))?),
+        })
+    }
+
+    /// Signs `data` on the security key, which asks the user to touch it.
+    pub async fn sign(&self, data: &[u8]) -> Result<Payload, Error> {
+        let credential = object(&[
+            ("type", JsValue::from_str("public-key")),
+            ("id", Uint8Array::from(self.credential_id.as_slice()).into()),
+        ])?;
+        let challenge = crate::webauthn::challenge(data);
+        let options = object(&[
+            ("challenge", Uint8Array::from(&challenge[..]).into()),
+            ("allowCredentials", Array::of1(&credential).into()),
+            ("userVerification", JsValue::from_str("discouraged")),
+        ])?;
+        let assertion = call_credentials("get", &object(&[("publicKey", options)])?).await?;
+
+        let response = Reflect::get(&assertion, &JsValue::from_str("response"))?;
+        let field = |name: &str| -> Result<Vec<u8>, Error> {
+            Ok(bytes(&Reflect::get(&response, &JsValue::from_str(name))?))
+        };
+        Ok(Payload::WebAuthn {
+            signature: field("signature")?,
+            authenticator_data: field("authenticatorData")?,
+            client_data: field("clientDataJSON")?,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Serializes the public key and the credential id to a JSON file.
+    pub async fn to_file(&self, subtle: &SubtleCrypto) -> Result<String, Error> {
+        let json = object(&[
+            ("type", JsValue::from_str(SECURITY_KEY_FILE_TYPE)),
+            (
+                "public",
+                crypto::export_key(subtle, &self.public_key).await?.into(),
+            ),
+            (
+                "credentialId",
+                JsValue::from_str(&base64::encode_config(
+                    &self.credential_id,
+                    base64::URL_SAFE_NO_PAD,
+                )),
+            ),
+        ])?;
+        Ok(js_sys::JSON::stringify(&json)?
+            .as_string()
+            .unwrap_or_default())
+    }
+
+    /// Reads a file written by [`to_file`](Self::to_file), returning `None`
+    /// for other files.
+    pub async fn from_file(subtle: &SubtleCrypto, data: &[u8]) -> Result<Option<Self>, Error> {
+        let json = match std::str::from_utf8(data)
+            .ok()
+            .and_then(|text| js_sys::JSON::parse(text).ok())
+        {
+            Some(json) if json.is_object() => json,
+            _ => return Ok(None),
+        };
+        let field = |name: &str| Reflect::get(&json, &JsValue::from_str(name));
+        if field("type")?.as_string().as_deref() != Some(SECURITY_KEY_FILE_TYPE) {
+            return Ok(None);
+        }
+        let not_a_security_key = || Error::malformed("This file is not a security key!");
+        let public = field("public")?;
+        if !public.is_object() {
+            return Err(not_a_security_key());
+        }
+        let credential_id = field("credentialId")?
+            .as_string()
+            .and_then(|id| base64::decode_config(id, base64::URL_SAFE_NO_PAD).ok())
+            .ok_or_else(not_a_security_key)?;
+        Ok(Some(Self {
+            public_key: crypto::import_public_key(subtle, public.unchecked_ref()).await?,
+            credential_id,
+        }))
+    }
+}
+
+/// Whether the browser supports WebAuthn.
+pub fn is_supported() -> bool {
+    credentials().is_ok_and(|credentials| !credentials.is_undefined())
+}
+
+fn credentials() -> Result<JsValue, JsValue> {
+    let navigator = Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))?;
+    Reflect::get(&navigator, &JsValue::from_str("credentials"))
+}
+
+/// Calls `navigator.credentials.create` or `get` and waits for the credential.
+async fn call_credentials(method: &str, options: &JsValue) -> Result<JsValue, Error> {
+    let credentials = credentials()?;
+    if credentials.is_undefined() {
+        return Err(Error::UnsupportedAlgorithm(
+            "The browser doesn't support security keys.".to_owned(),
+        ));
+    }
+    let promise = Reflect::get(&credentials, &JsValue::from_str(method))?
+        .unchecked_into::<Function>()
+        .call1(&credentials, options)?;
+    let credential = JsFuture::from(promise.unchecked_into::<Promise>()).await?;
+    if credential.is_null() {
+        return Err(Error::Crypto("No credential was returned.".to_owned()));
+    }
+    Ok(credential)
+}
+
+fn object(fields: &[(&str, JsValue)]) -> Result<JsValue, JsValue> {
+    let object = Object::new();
+    for (name, value) in fields {
+        Reflect::set(&object, &JsValue::from_str(name), value)?;
+    }
+    Ok(object.into())
+}
+
+/// Copies an `ArrayBuffer` or typed array.
+fn bytes(buffer: &JsValue) -> Vec<u8> {
+    Uint8Array::new(buffer).to_vec()
+}
+
+fn random_bytes(size: usize) -> Result<Uint8Array, JsValue> {
+    let mut bytes = vec![0; size];
+    web_sys::window()
+        .ok_or_else(|| JsValue::from_str("No window"))?
+        .crypto()?
+        .get_random_values_with_u8_array(&mut bytes)?;
+    Ok(Uint8Array::from(bytes.as_slice()))
+}
@@ -0,0 +1,210 @@
+use crate::{
+    metadata::{self, Metadata},
+    qr_reader::Verdict,
+};
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+use yew::prelude::*;
+
+/// A scanned code with its result.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub scanned_at: f64,
+    pub verdict: Verdict,
+}
+
+/// Lists the results of all scans, filtered by content, result and metadata.
+pub struct VerificationLog {
+    link: ComponentLink<Self>,
+    entries: Rc<Vec<LogEntry>>,
+    onclear: Callback<()>,
+    filter: Filter,
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    /// Oldest first.
+    pub entries: Rc<Vec<LogEntry>>,
+    pub onclear: Callback<()>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Any,
+    Valid,
+    Failed,
+}
+
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Any, Outcome::Valid, Outcome::Failed];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Any => "All results",
+            Self::Valid => "Valid",
+            Self::Failed => "Failed",
+        }
+    }
+}
+
+/// All text filters match case-insensitively anywhere in the field.
+struct Filter {
+    outcome: Outcome,
+    text: String,
+    issued: String,
+    station: String,
+    batch: String,
+}
+
+impl Filter {
+    fn matches(&self, verdict: &Verdict) -> bool {
+        let (valid, text, metadata) = match verdict {
+            Verdict::Verified {
+                payload, metadata, ..
+            } => (true, payload.as_str(), Some(metadata)),
+            Verdict::Failed(reason) => (false, reason.as_str(), None),
+        };
+        let outcome = match self.outcome {
+            Outcome::Any => true,
+            Outcome::Valid => valid,
+            Outcome::Failed => !valid,
+        };
+        let issued = metadata
+            .and_then(|metadata| metadata.issued_at)
+            .map(metadata::format_timestamp);
+        outcome
+            && contains(Some(text), &self.text)
+            && contains(issued.as_deref(), &self.issued)
+            && contains(
+                metadata.and_then(|metadata| metadata.station.as_deref()),
+                &self.station,
+            )
+            && contains(
+                metadata.and_then(|metadata| metadata.batch.as_deref()),
+                &self.batch,
+            )
+    }
+}
+
+fn contains(field: Option<&str>, filter: &str) -> bool {
+    filter.is_empty()
+        || field
+            .map(|field| field.to_lowercase().contains(&filter.to_lowercase()))
+            .unwrap_or(false)
+}
+
+pub enum Msg {
+    SetOutcome(Outcome),
+    SetText(String),
+    SetIssued(String),
+    SetStation(String),
+    SetBatch(String),
+    Clear,
+}
+
+impl Component for VerificationLog {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            entries: props.entries,
+            onclear: props.onclear,
+            filter: Filter {
+                outcome: Outcome::Any,
+                text: String::new(),
+                issued: String::new(),
+                station: String::new(),
+                batch: String::new(),
+            },
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::SetOutcome(outcome) => self.filter.outcome = outcome,
+            Msg::SetText(text) => self.filter.text = text,
+            Msg::SetIssued(issued) => self.filter.issued = issued,
+            Msg::SetStation(station) => self.filter.station = station,
+            Msg::SetBatch(batch) => self.filter.batch = batch,
+            Msg::Clear => {
+                self.onclear.emit(());
+                return false;
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.entries = props.entries;
+        self.onclear = props.onclear;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="verification_log">
+                <div class="log_filter">
+                    <select onchange=self.link.batch_callback(|data: ChangeData| match data {
+                        ChangeData::Select(select) => Outcome::ALL
+                            .iter()
+                            .find(|outcome| outcome.name() == select.value())
+                            .map(|outcome| vec![Msg::SetOutcome(*outcome)])
+                            .unwrap_or_default(),
+                        _ => vec![],
+                    })>
+                        { for Outcome::ALL.iter().map(|outcome| html! {
+                            <option value=outcome.name() selected={ *outcome == self.filter.outcome }>{ outcome.name() }</option>
+                        }) }
+                    </select>
+                    <input type="search" placeholder="Content" value=&self.filter.text oninput=self.link.callback(|data: InputData| Msg::SetText(data.value)) />
+                    <input type="search" placeholder="Issued (e.g. 2024-05-01)" value=&self.filter.issued oninput=self.link.callback(|data: InputData| Msg::SetIssued(data.value)) />
+                    <input type="search" placeholder="Station" value=&self.filter.station oninput=self.link.callback(|data: InputData| Msg::SetStation(data.value)) />
+                    <input type="search" placeholder="Batch" value=&self.filter.batch oninput=self.link.callback(|data: InputData| Msg::SetBatch(data.value)) />
+                    <button onclick=self.link.callback(|_| Msg::Clear) class="mdi-set mdi-delete-sweep" title="Clear Log"></button>
+                </div>
+                <table>
+                    <thead>
+                        <tr>
+                            <th>{ "Scanned" }</th>
+                            <th>{ "Result" }</th>
+                            <th>{ "Content" }</th>
+                            <th>{ "Issued" }</th>
+                            <th>{ "Station" }</th>
+                            <th>{ "Batch" }</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for self.entries.iter().rev().filter(|entry| self.filter.matches(&entry.verdict)).map(view_entry) }
+                    </tbody>
+                </table>
+            </div>
+        }
+    }
+}
+
+fn view_entry(entry: &LogEntry) -> Html {
+    let scanned_at = js_sys::Date::new(&JsValue::from(entry.scanned_at))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .as_string()
+        .unwrap_or_default();
+    let empty = Metadata::default();
+    let (class, result, text, metadata) = match &entry.verdict {
+        Verdict::Verified {
+            payload, metadata, ..
+        } => ("valid", "Valid", payload, metadata),
+        Verdict::Failed(reason) => ("failed", "Failed", reason, &empty),
+    };
+    html! {
+        <tr class=class>
+            <td>{ scanned_at }</td>
+            <td>{ result }</td>
+            <td>{ text }</td>
+            <td>{ metadata.issued_at.map(metadata::format_timestamp).unwrap_or_default() }</td>
+            <td>{ metadata.station.clone().unwrap_or_default() }</td>
+            <td>{ metadata.batch.clone().unwrap_or_default() }</td>
+        </tr>
+    }
+}
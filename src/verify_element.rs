@@ -0,0 +1,315 @@
+//! The `<qr-verify>` custom element for embedding a verifier into other pages.
+//!
+//! The element itself is defined in `js/qr-verify.js` and mounts [`Verifier`]
+//! into itself through [`mount_verifier`].
+
+use crate::{
+    crypto,
+    fingerprint::{self, Fingerprint},
+    key_file::KeyFile,
+    qr_reader::{QrReader, Verdict},
+};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use web_sys::{CryptoKey, CustomEvent, CustomEventInit, Element};
+use yew::prelude::*;
+
+/// Name of the event dispatched on the element for every scanned code.
+const VERIFY_EVENT: &str = "verify";
+
+pub struct Verifier {
+    link: ComponentLink<Self>,
+    element: Element,
+    trusted_keys: Vec<Fingerprint>,
+    kiosk: bool,
+    public_key: Option<CryptoKey>,
+    previous_keys: Vec<CryptoKey>,
+    /// The `cosigner-keys` attribute the keys were imported from.
+    cosigner_text: String,
+    cosigner_keys: Vec<CryptoKey>,
+    threshold: usize,
+    mounted: bool,
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    pub element: Element,
+    pub trusted_keys: Vec<Fingerprint>,
+    #[prop_or_default]
+    pub kiosk: bool,
+    #[prop_or_default]
+    pub cosigner_keys: String,
+    #[prop_or(1)]
+    pub threshold: usize,
+}
+
+pub enum Msg {
+    SetPublicKey(CryptoKey),
+    SetSuccessor(CryptoKey, CryptoKey),
+    Verified(Verdict),
+    SetTrustedKeys(Vec<Fingerprint>),
+    SetKiosk(bool),
+    SetCosignerKeys(String),
+    CosignerKeysImported(String, Vec<CryptoKey>),
+    SetThreshold(usize),
+    Unmount,
+}
+
+impl Component for Verifier {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        link.send_message(Msg::SetCosignerKeys(props.cosigner_keys));
+        Self {
+            link,
+            element: props.element,
+            trusted_keys: props.trusted_keys,
+            kiosk: props.kiosk,
+            public_key: None,
+            previous_keys: Vec::new(),
+            cosigner_text: String::new(),
+            cosigner_keys: Vec::new(),
+            threshold: props.threshold.max(1),
+            mounted: true,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::SetPublicKey(public_key) => {
+                self.public_key = Some(public_key);
+                self.previous_keys.clear();
+                true
+            }
+            Msg::SetSuccessor(old_key, new_key) => {
+                // The rotated key isn't necessarily the loaded one, as rotations of
+                // any trusted key are accepted.
+                self.previous_keys.insert(0, old_key);
+                self.public_key = Some(new_key);
+                true
+            }
+            Msg::Verified(verdict) => {
+                if let Err(err) = self.dispatch(&verdict) {
+                    web_sys::console::log_2(&JsValue::from_str("Failed dispatching event"), &err);
+                }
+                false
+            }
+            Msg::SetTrustedKeys(trusted_keys) => {
+                // A key imported before may not be trusted anymore.
+                self.public_key = None;
+                self.previous_keys.clear();
+                self.trusted_keys = trusted_keys;
+                true
+            }
+            Msg::SetKiosk(kiosk) => {
+                self.kiosk = kiosk;
+                true
+            }
+            Msg::SetCosignerKeys(text) => {
+                self.cosigner_text = text.clone();
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let keys = import_cosigner_keys(&text).await;
+                    link.send_message(Msg::CosignerKeysImported(text, keys));
+                });
+                false
+            }
+            Msg::CosignerKeysImported(text, keys) => {
+                // The attribute may have changed again while importing.
+                if text != self.cosigner_text {
+                    return false;
+                }
+                self.cosigner_keys = keys;
+                true
+            }
+            Msg::SetThreshold(threshold) => {
+                self.threshold = threshold.max(1);
+                true
+            }
+            Msg::Unmount => {
+                self.mounted = false;
+                true
+            }
+        }
+    }
+
+    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+        false
+    }
+
+    fn view(&self) -> Html {
+        if !self.mounted {
+            return html! {};
+        }
+        html! {
+            <QrReader
+                onpublickey=self.link.callback(Msg::SetPublicKey)
+                public_key=self.public_key.clone()
+                previous_keys=self.previous_keys.clone()
+                onsuccessor=self.link.callback(|(old_key, new_key)| Msg::SetSuccessor(old_key, new_key))
+                kiosk=self.kiosk
+                cosigner_keys=self.cosigner_keys.clone()
+                threshold=self.threshold
+                trusted_keys=Some(self.trusted_keys.clone())
+                onverdict=Some(self.link.callback(Msg::Verified)) />
+        }
+    }
+}
+
+impl Verifier {
+    /// Dispatches a `verify` event with `{ valid, payload, signers }` or
+    /// `{ valid, reason }` as detail. Valid codes with metadata add `issuedAt`
+    /// (a `Date`), `station` and `batch`.
+    fn dispatch(&self, verdict: &Verdict) -> Result<(), JsValue> {
+        let detail = Object::new();
+        match verdict {
+            Verdict::Verified {
+                payload,
+                signers,
+                metadata,
+            } => {
+                Reflect::set(&detail, &JsValue::from_str("valid"), &JsValue::TRUE)?;
+                Reflect::set(
+                    &detail,
+                    &JsValue::from_str("payload"),
+                    &JsValue::from_str(payload),
+                )?;
+                let signers: Array = signers
+                    .iter()
+                    .map(|signer| JsValue::from_str(&fingerprint::to_hex(&signer.fingerprint)))
+                    .collect();
+                Reflect::set(&detail, &JsValue::from_str("signers"), &signers)?;
+                if let Some(issued_at) = metadata.issued_at {
+                    let issued_at = js_sys::Date::new(&JsValue::from(issued_at as f64 * 1000.0));
+                    Reflect::set(&detail, &JsValue::from_str("issuedAt"), &issued_at)?;
+                }
+                for (name, value) in &[("station", &metadata.station), ("batch", &metadata.batch)] {
+                    if let Some(value) = value {
+                        Reflect::set(&detail, &JsValue::from_str(name), &JsValue::from_str(value))?;
+                    }
+                }
+            }
+            Verdict::Failed(reason) => {
+                Reflect::set(&detail, &JsValue::from_str("valid"), &JsValue::FALSE)?;
+                Reflect::set(
+                    &detail,
+                    &JsValue::from_str("reason"),
+                    &JsValue::from_str(reason),
+                )?;
+            }
+        }
+
+        let init = CustomEventInit::new();
+        init.set_bubbles(true);
+        init.set_composed(true);
+        init.set_detail(&detail);
+        let event = CustomEvent::new_with_event_init_dict(VERIFY_EVENT, &init)?;
+        self.element.dispatch_event(&event)?;
+        Ok(())
+    }
+}
+
+/// Splits an attribute listing entries separated by whitespace or commas.
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|entry| !entry.is_empty())
+}
+
+/// Parses a list of fingerprints separated by whitespace or commas, written as
+/// hex with optional colons. Invalid entries are logged and skipped.
+fn parse_trusted_keys(text: &str) -> Vec<Fingerprint> {
+    entries(text)
+        .filter_map(|entry| {
+            let fingerprint = fingerprint::parse_hex(entry);
+            if fingerprint.is_none() {
+                web_sys::console::log_2(
+                    &JsValue::from_str("Ignoring invalid fingerprint"),
+                    &JsValue::from_str(entry),
+                );
+            }
+            fingerprint
+        })
+        .collect()
+}
+
+/// Imports the public keys of co-signers, given as base64 (raw point or SPKI)
+/// separated by whitespace or commas. Invalid entries are logged and skipped.
+async fn import_cosigner_keys(text: &str) -> Vec<CryptoKey> {
+    let mut keys = Vec::new();
+    let subtle = match crypto::subtle() {
+        Ok(subtle) => subtle,
+        Err(err) => {
+            err.report();
+            return keys;
+        }
+    };
+    for entry in entries(text) {
+        let key = match KeyFile::parse(entry.as_bytes()) {
+            Ok(key_file) => crypto::import_key_file(&subtle, &key_file).await,
+            Err(err) => Err(err),
+        };
+        match key {
+            Ok((public_key, _)) => keys.push(public_key),
+            Err(err) => web_sys::console::log_2(
+                &JsValue::from_str("Ignoring invalid co-signer key"),
+                &JsValue::from_str(&format!("{}: {}", entry, err)),
+            ),
+        }
+    }
+    keys
+}
+
+/// Handle of a mounted verifier, owned by the custom element.
+#[wasm_bindgen]
+pub struct VerifierHandle {
+    link: ComponentLink<Verifier>,
+}
+
+#[wasm_bindgen]
+impl VerifierHandle {
+    pub fn set_trusted_keys(&self, trusted_keys: &str) {
+        self.link
+            .send_message(Msg::SetTrustedKeys(parse_trusted_keys(trusted_keys)));
+    }
+
+    pub fn set_kiosk(&self, kiosk: bool) {
+        self.link.send_message(Msg::SetKiosk(kiosk));
+    }
+
+    pub fn set_cosigner_keys(&self, cosigner_keys: &str) {
+        self.link
+            .send_message(Msg::SetCosignerKeys(cosigner_keys.to_owned()));
+    }
+
+    pub fn set_threshold(&self, threshold: u32) {
+        self.link
+            .send_message(Msg::SetThreshold(threshold as usize));
+    }
+
+    /// Stops the camera and removes the verifier from the element.
+    pub fn destroy(&self) {
+        self.link.send_message(Msg::Unmount);
+    }
+}
+
+/// Mounts a verifier into `element`, see `js/qr-verify.js`.
+#[wasm_bindgen]
+pub fn mount_verifier(
+    element: Element,
+    trusted_keys: &str,
+    kiosk: bool,
+    cosigner_keys: &str,
+    threshold: u32,
+) -> VerifierHandle {
+    let props = Props {
+        element: element.clone(),
+        trusted_keys: parse_trusted_keys(trusted_keys),
+        kiosk,
+        cosigner_keys: cosigner_keys.to_owned(),
+        threshold: threshold as usize,
+    };
+    VerifierHandle {
+        link: App::<Verifier>::new().mount_with_props(element, props),
+    }
+}
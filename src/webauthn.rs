@@ -0,0 +1,65 @@
+//! Signatures made by FIDO2 authenticators through WebAuthn, e.g. hardware
+//! security keys that never reveal their private key.
+//!
+//! Authenticators don't sign the data itself, but their authenticator data
+//! followed by the SHA-256 hash of the client data. The client data is a JSON
+//! object made by the browser, which contains the challenge. The challenge is
+//! the SHA-256 hash of the data, so the signature covers the data as well.
+//! `WAUTH:` codes carry all three, see
+//! [`Payload::WebAuthn`](crate::payload::Payload::WebAuthn).
+
+use crate::error::Error;
+
+/// `type` of the client data of assertions.
+pub const ASSERTION_TYPE: &str = "webauthn.get";
+
+/// Size of the hash of the relying party id, the flags and the signature
+/// counter, which start every authenticator data.
+pub const AUTHENTICATOR_DATA_MIN_SIZE: usize = 37;
+
+/// Offset of the flags in the authenticator data.
+const FLAGS: usize = 32;
+/// Flag set if the user touched the authenticator.
+const USER_PRESENT: u8 = 0x01;
+
+/// The challenge to sign `data` with.
+pub fn challenge(data: &[u8]) -> [u8; 32] {
+    hmac_sha256::Hash::hash(data)
+}
+
+/// The start of the client data for signing `data`.
+///
+/// Browsers always serialize `type` and `challenge` first and in this order,
+/// so the client data can be checked without parsing the JSON.
+pub fn client_data_prefix(data: &[u8]) -> String {
+    format!(
+        r#"{{"type":"{}","challenge":"{}""#,
+        ASSERTION_TYPE,
+        base64::encode_config(challenge(data), base64::URL_SAFE_NO_PAD)
+    )
+}
+
+/// Checks that an assertion was made for `data` with the user present.
+///
+/// The signature itself is checked over [`signed_message`].
+pub fn check(authenticator_data: &[u8], client_data: &[u8], data: &[u8]) -> Result<(), Error> {
+    if authenticator_data.len() < AUTHENTICATOR_DATA_MIN_SIZE {
+        return Err(Error::malformed("The authenticator data is truncated."));
+    }
+    if authenticator_data[FLAGS] & USER_PRESENT == 0 {
+        return Err(Error::malformed("The security key wasn't touched."));
+    }
+    if !client_data.starts_with(client_data_prefix(data).as_bytes()) {
+        return Err(Error::malformed("The assertion was made for other data."));
+    }
+    Ok(())
+}
+
+/// Returns the message signed by the authenticator.
+pub fn signed_message(authenticator_data: &[u8], client_data: &[u8]) -> Vec<u8> {
+    [
+        authenticator_data,
+        hmac_sha256::Hash::hash(client_data).as_ref(),
+    ]
+    .concat()
+}
@@ -0,0 +1,343 @@
+use crate::{
+    batch::{self, Progress},
+    crypto::{subtle, Signer},
+    error::Error,
+    metadata::Metadata,
+    qr_generator::Encoding,
+};
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{CryptoKey, DedicatedWorkerGlobalScope, MessageEvent, Worker};
+use yew::Callback;
+
+/// Script that boots the wasm module inside the worker, see `js/worker.js`.
+const WORKER_SCRIPT: &str = "worker.js";
+
+pub enum Request {
+    SignBatch {
+        job: u32,
+        private_key: CryptoKey,
+        text: String,
+        metadata: Metadata,
+        /// Sign with RFC 6979, see [`Signer`].
+        deterministic: bool,
+        encoding: Encoding,
+    },
+    Cancel {
+        job: u32,
+    },
+}
+
+#[derive(Debug)]
+pub enum Response {
+    Progress {
+        job: u32,
+        progress: Progress,
+    },
+    Finished {
+        job: u32,
+        zip: Vec<u8>,
+        failed: usize,
+    },
+    Cancelled {
+        job: u32,
+    },
+    Failed {
+        job: u32,
+        message: String,
+    },
+}
+
+// Messages are plain objects instead of serde structures, so that keys can be
+// passed as `CryptoKey` and archives can be transferred without copying.
+
+fn get(message: &JsValue, name: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(message, &JsValue::from_str(name))
+}
+
+fn get_u32(message: &JsValue, name: &str) -> Result<u32, JsValue> {
+    get(message, name)?
+        .as_f64()
+        .map(|value| value as u32)
+        .ok_or_else(|| JsValue::from_str(&format!("Missing field {}", name)))
+}
+
+fn optional_string(value: &Option<String>) -> JsValue {
+    value
+        .as_deref()
+        .map(JsValue::from_str)
+        .unwrap_or(JsValue::UNDEFINED)
+}
+
+fn message(kind: &str, job: u32, fields: &[(&str, &JsValue)]) -> Result<Object, JsValue> {
+    let message = Object::new();
+    Reflect::set(
+        &message,
+        &JsValue::from_str("type"),
+        &JsValue::from_str(kind),
+    )?;
+    Reflect::set(&message, &JsValue::from_str("job"), &JsValue::from(job))?;
+    for (name, value) in fields {
+        Reflect::set(&message, &JsValue::from_str(name), value)?;
+    }
+    Ok(message)
+}
+
+impl Request {
+    fn to_js(&self) -> Result<Object, JsValue> {
+        match self {
+            Self::SignBatch {
+                job,
+                private_key,
+                text,
+                metadata,
+                deterministic,
+                encoding,
+            } => message(
+                "signBatch",
+                *job,
+                &[
+                    ("privateKey", private_key),
+                    ("text", &JsValue::from_str(text)),
+                    (
+                        "issuedAt",
+                        &metadata
+                            .issued_at
+                            .map(|issued_at| JsValue::from(issued_at as f64))
+                            .unwrap_or(JsValue::UNDEFINED),
+                    ),
+                    ("station", &optional_string(&metadata.station)),
+                    ("batch", &optional_string(&metadata.batch)),
+                    ("deterministic", &JsValue::from(*deterministic)),
+                    ("encoding", &JsValue::from_str(encoding.name())),
+                    (
+                        "baseUrl",
+                        &match encoding {
+                            Encoding::Url(base_url) => JsValue::from_str(base_url),
+                            _ => JsValue::UNDEFINED,
+                        },
+                    ),
+                ],
+            ),
+            Self::Cancel { job } => message("cancel", *job, &[]),
+        }
+    }
+
+    fn from_js(message: &JsValue) -> Result<Self, JsValue> {
+        let job = get_u32(message, "job")?;
+        match get(message, "type")?.as_string().as_deref() {
+            Some("signBatch") => Ok(Self::SignBatch {
+                job,
+                private_key: get(message, "privateKey")?.dyn_into()?,
+                text: get(message, "text")?.as_string().unwrap_or_default(),
+                metadata: Metadata {
+                    issued_at: get(message, "issuedAt")?
+                        .as_f64()
+                        .map(|issued_at| issued_at as u64),
+                    station: get(message, "station")?.as_string(),
+                    batch: get(message, "batch")?.as_string(),
+                },
+                deterministic: get(message, "deterministic")?.is_truthy(),
+                encoding: Encoding::from_name(
+                    &get(message, "encoding")?.as_string().unwrap_or_default(),
+                    &get(message, "baseUrl")?.as_string().unwrap_or_default(),
+                )
+                .unwrap_or_default(),
+            }),
+            Some("cancel") => Ok(Self::Cancel { job }),
+            _ => Err(JsValue::from_str("Unknown request")),
+        }
+    }
+}
+
+impl Response {
+    /// Returns the message and the buffers that can be transferred along with it.
+    fn to_js(&self) -> Result<(Object, Array), JsValue> {
+        let transfer = Array::new();
+        let message = match self {
+            Self::Progress { job, progress } => message(
+                "progress",
+                *job,
+                &[
+                    ("signed", &JsValue::from(progress.signed as u32)),
+                    ("rendered", &JsValue::from(progress.rendered as u32)),
+                    ("total", &JsValue::from(progress.total as u32)),
+                ],
+            )?,
+            Self::Finished { job, zip, failed } => {
+                let zip = Uint8Array::from(zip.as_slice());
+                transfer.push(&zip.buffer());
+                message(
+                    "finished",
+                    *job,
+                    &[("zip", &zip), ("failed", &JsValue::from(*failed as u32))],
+                )?
+            }
+            Self::Cancelled { job } => message("cancelled", *job, &[])?,
+            Self::Failed { job, message: text } => {
+                message("failed", *job, &[("message", &JsValue::from_str(text))])?
+            }
+        };
+        Ok((message, transfer))
+    }
+
+    fn from_js(message: &JsValue) -> Result<Self, JsValue> {
+        let job = get_u32(message, "job")?;
+        match get(message, "type")?.as_string().as_deref() {
+            Some("progress") => Ok(Self::Progress {
+                job,
+                progress: Progress {
+                    signed: get_u32(message, "signed")? as usize,
+                    rendered: get_u32(message, "rendered")? as usize,
+                    total: get_u32(message, "total")? as usize,
+                },
+            }),
+            Some("finished") => Ok(Self::Finished {
+                job,
+                zip: get(message, "zip")?.unchecked_into::<Uint8Array>().to_vec(),
+                failed: get_u32(message, "failed")? as usize,
+            }),
+            Some("cancelled") => Ok(Self::Cancelled { job }),
+            Some("failed") => Ok(Self::Failed {
+                job,
+                message: get(message, "message")?.as_string().unwrap_or_default(),
+            }),
+            _ => Err(JsValue::from_str("Unknown response")),
+        }
+    }
+}
+
+/// Runs inside the worker and serves requests until the worker is terminated.
+pub fn run() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let cancelled = Rc::new(Cell::new(None));
+
+    let reply_scope = scope.clone();
+    let onmessage = Closure::wrap(Box::new(move |event: MessageEvent| {
+        let request = match Request::from_js(&event.data()) {
+            Ok(request) => request,
+            Err(err) => {
+                web_sys::console::log_2(&JsValue::from_str("Invalid request"), &err);
+                return;
+            }
+        };
+        match request {
+            Request::Cancel { job } => cancelled.set(Some(job)),
+            Request::SignBatch {
+                job,
+                private_key,
+                text,
+                metadata,
+                deterministic,
+                encoding,
+            } => {
+                let scope = reply_scope.clone();
+                let cancelled = cancelled.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = async {
+                        let subtle = subtle()?;
+                        let signer = Signer::new(&subtle, &private_key, deterministic).await?;
+                        batch::sign_batch(
+                            &subtle,
+                            &signer,
+                            &text,
+                            &metadata,
+                            encoding,
+                            |progress| reply(&scope, &Response::Progress { job, progress }),
+                            || cancelled.get() == Some(job),
+                        )
+                        .await
+                    }
+                    .await;
+                    let response = match result {
+                        Ok(Some(signed)) => Response::Finished {
+                            job,
+                            zip: signed.zip,
+                            failed: signed.failed,
+                        },
+                        Ok(None) => Response::Cancelled { job },
+                        Err(err) => Response::Failed {
+                            job,
+                            message: err.to_string(),
+                        },
+                    };
+                    reply(&scope, &response);
+                });
+            }
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    scope.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+}
+
+fn reply(scope: &DedicatedWorkerGlobalScope, response: &Response) {
+    let result = response
+        .to_js()
+        .and_then(|(message, transfer)| scope.post_message_with_transfer(&message, &transfer));
+    if let Err(err) = result {
+        web_sys::console::log_2(&JsValue::from_str("Failed posting response"), &err);
+    }
+}
+
+/// Handle to the batch signing worker, used from the UI thread.
+pub struct BatchWorker {
+    worker: Worker,
+    next_job: u32,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl BatchWorker {
+    pub fn new(onresponse: Callback<Response>) -> Result<Self, Error> {
+        let worker = Worker::new(WORKER_SCRIPT)?;
+        let onmessage = Closure::wrap(Box::new(move |event: MessageEvent| {
+            match Response::from_js(&event.data()) {
+                Ok(response) => onresponse.emit(response),
+                Err(err) => web_sys::console::log_2(&JsValue::from_str("Invalid response"), &err),
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        Ok(Self {
+            worker,
+            next_job: 0,
+            _onmessage: onmessage,
+        })
+    }
+
+    /// Starts signing all lines of `text` and returns the job id used in responses.
+    pub fn sign_batch(
+        &mut self,
+        private_key: &CryptoKey,
+        text: String,
+        metadata: Metadata,
+        deterministic: bool,
+        encoding: Encoding,
+    ) -> Result<u32, Error> {
+        self.next_job += 1;
+        let job = self.next_job;
+        self.post(&Request::SignBatch {
+            job,
+            private_key: private_key.clone(),
+            text,
+            metadata,
+            deterministic,
+            encoding,
+        })?;
+        Ok(job)
+    }
+
+    pub fn cancel(&self, job: u32) -> Result<(), Error> {
+        self.post(&Request::Cancel { job })
+    }
+
+    fn post(&self, request: &Request) -> Result<(), Error> {
+        self.worker.post_message(&request.to_js()?.into())?;
+        Ok(())
+    }
+}
+
+impl Drop for BatchWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
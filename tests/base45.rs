@@ -0,0 +1,46 @@
+use qr_signing::{base45, payload::SIGNATURE_SIZE, Error, Payload};
+
+// Examples from RFC 9285, section 4.
+const EXAMPLES: [(&str, &str); 4] = [
+    ("AB", "BB8"),
+    ("Hello!!", "%69 VD92EX0"),
+    ("base-45", "UJCLQE7W581"),
+    ("ietf!", "QED8WEX0"),
+];
+
+#[test]
+fn encodes_examples() {
+    for (data, text) in EXAMPLES.iter() {
+        assert_eq!(base45::encode(data.as_bytes()), *text);
+        assert_eq!(base45::decode(text), Ok(data.as_bytes().to_vec()));
+    }
+}
+
+#[test]
+fn round_trips_all_bytes() {
+    let data: Vec<u8> = (0..=255).collect();
+    assert_eq!(base45::decode(&base45::encode(&data)), Ok(data));
+}
+
+#[test]
+fn rejects_invalid_text() {
+    // 65536 doesn't fit into two bytes, 256 not into one.
+    assert!(matches!(
+        base45::decode("GGW"),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(base45::decode("A7").is_err());
+    assert!(base45::decode("BB8B").is_err());
+    assert!(base45::decode("bb8").is_err());
+}
+
+#[test]
+fn parses_base45_payloads() {
+    let payload = Payload::Signed {
+        signature: (0..SIGNATURE_SIZE as u8).collect(),
+        data: b"Ticket #42".to_vec(),
+    };
+    let text = base45::encode(&payload.to_bytes());
+    assert_eq!(Payload::parse(text.as_bytes()), Ok(payload.clone()));
+    assert_eq!(Payload::parse(&payload.to_bytes()), Ok(payload));
+}
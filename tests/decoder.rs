@@ -0,0 +1,102 @@
+#![cfg(feature = "rust-decoder")]
+
+use qr_signing::{
+    decoder,
+    qr_generator::{encode_qr_as, Encoding},
+    Payload,
+};
+use qrcodegen::{QrCode, QrCodeEcc};
+
+/// Parses a binary (P5) PGM image into width, height and luminance.
+fn load_pgm(bytes: &[u8]) -> (usize, usize, Vec<u8>) {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while fields.len() < 4 {
+        while bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        fields.push(std::str::from_utf8(&bytes[start..pos]).unwrap().to_owned());
+    }
+    assert_eq!(fields[0], "P5");
+    let width = fields[1].parse().unwrap();
+    let height = fields[2].parse().unwrap();
+    (width, height, bytes[pos + 1..].to_vec())
+}
+
+/// Renders a QR code as luminance with the given module size and quiet zone.
+fn render(qr: &QrCode, scale: i32, border: i32) -> (usize, usize, Vec<u8>) {
+    let size = (qr.size() + 2 * border) * scale;
+    let mut luma = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let dark = qr.get_module(x / scale - border, y / scale - border);
+            luma.push(if dark { 0 } else { 255 });
+        }
+    }
+    (size as usize, size as usize, luma)
+}
+
+#[test]
+fn decodes_fixture_image() {
+    let (width, height, luma) = load_pgm(include_bytes!("fixtures/signed.pgm"));
+
+    let mut expected = b"SIGN:".to_vec();
+    expected.extend((0u8..64).map(|b| b.wrapping_mul(37).wrapping_add(0x80)));
+    expected.extend_from_slice("Ticket #42 – Grüße".as_bytes());
+
+    assert_eq!(decoder::decode_luma(width, height, &luma), vec![expected]);
+}
+
+#[test]
+fn keeps_binary_data_intact() {
+    let data: Vec<u8> = (0..=255).collect();
+    let qr = QrCode::encode_binary(&data, QrCodeEcc::Low).unwrap();
+    let (width, height, luma) = render(&qr, 3, 4);
+
+    assert_eq!(decoder::decode_luma(width, height, &luma), vec![data]);
+}
+
+#[test]
+fn decodes_mirrored_image() {
+    let data = b"PUB:mirrored".to_vec();
+    let qr = QrCode::encode_binary(&data, QrCodeEcc::Low).unwrap();
+    let (width, height, luma) = render(&qr, 4, 4);
+    let mirrored: Vec<u8> = luma
+        .chunks_exact(width)
+        .flat_map(|row| row.iter().rev().copied())
+        .collect();
+
+    assert_eq!(decoder::decode_luma(width, height, &mirrored), vec![data]);
+}
+
+#[test]
+fn reads_base45_codes() {
+    let payload = Payload::Signed {
+        signature: vec![0xFF; 64],
+        data: "Grüße".as_bytes().to_vec(),
+    };
+    let qr = encode_qr_as(&payload.to_bytes(), &Encoding::Base45).unwrap();
+    let (width, height, luma) = render(&qr, 3, 4);
+
+    let decoded = decoder::decode_luma(width, height, &luma);
+    assert_eq!(decoded.len(), 1);
+    assert!(decoded[0].iter().all(u8::is_ascii));
+    assert_eq!(Payload::parse(&decoded[0]), Ok(payload));
+}
+
+#[test]
+fn converts_rgba_to_luma() {
+    let rgba = [255, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255];
+    assert_eq!(decoder::rgba_to_luma(&rgba), vec![255, 0, 76]);
+}
+
+#[test]
+fn ignores_empty_frames() {
+    assert!(decoder::decode_luma(0, 0, &[]).is_empty());
+    assert!(decoder::decode_luma(10, 10, &[255; 10]).is_empty());
+    assert!(decoder::decode_luma(10, 10, &[255; 100]).is_empty());
+}
@@ -0,0 +1,290 @@
+use qr_signing::{
+    crypto::{self, SignatureEncoding},
+    ecdsa, payload, Error, Payload,
+};
+
+// Test vector from RFC 6979, A.2.5 (P-256 with SHA-256, message "sample").
+const PRIVATE_KEY: &str = "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721";
+const PUBLIC_KEY: &str = "04\
+    60FED4BA255A9D31C961EB74C6356D68C049B8923B61FA6CE669622E60F29FB6\
+    7903FE1008B8BC99A41AE9E95628BC64F2F1B20C2D7E9F5177A3C294D4462299";
+const SIGNATURE: &str = "\
+    EFD48B2AACB6A8FD1140DD9CD45E81D69D2C877B56AAF991C34D0EA84EAF3716\
+    F7CB1C942D657C41D436C7A1B6E29F65F3E900DBB9AFF4064DC4AB2F843ACDA8";
+/// Order of the P-256 group.
+const ORDER: &str = "FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551";
+
+fn hex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&text[idx..idx + 2], 16).unwrap())
+        .collect()
+}
+
+/// Returns the same signature with `s` replaced by `n - s`, which is just as
+/// valid and may be produced by WebCrypto implementations.
+fn negate_s(signature: &[u8]) -> Vec<u8> {
+    let order = hex(ORDER);
+    let mut result = signature.to_vec();
+    let mut borrow = 0i16;
+    for idx in (0..32).rev() {
+        let mut digit = order[idx] as i16 - signature[32 + idx] as i16 - borrow;
+        borrow = if digit < 0 { 1 } else { 0 };
+        if digit < 0 {
+            digit += 256;
+        }
+        result[32 + idx] = digit as u8;
+    }
+    result
+}
+
+#[test]
+fn derives_public_key() {
+    let key_pair = ecdsa::KeyPair::from_private_key(&hex(PRIVATE_KEY)).unwrap();
+    assert_eq!(key_pair.public_key(), hex(PUBLIC_KEY));
+    assert_eq!(key_pair.private_key(), hex(PRIVATE_KEY));
+}
+
+#[test]
+fn compresses_public_key() {
+    // `y` of the test vector is odd.
+    let compressed = hex(&format!("03{}", &PUBLIC_KEY[2..66]));
+    assert_eq!(
+        ecdsa::compress_public_key(&hex(PUBLIC_KEY)),
+        Ok(compressed.clone())
+    );
+    assert_eq!(
+        ecdsa::decompress_public_key(&compressed),
+        Ok(hex(PUBLIC_KEY))
+    );
+    assert_eq!(
+        ecdsa::verify(&compressed, &hex(SIGNATURE), b"sample"),
+        Ok(true)
+    );
+    assert!(ecdsa::decompress_public_key(&compressed[..32]).is_err());
+}
+
+#[test]
+fn signs_test_vector() {
+    let key_pair = ecdsa::KeyPair::from_private_key(&hex(PRIVATE_KEY)).unwrap();
+    assert_eq!(key_pair.sign(b"sample"), hex(SIGNATURE));
+}
+
+#[test]
+fn signs_reproducibly() {
+    let key_pair = ecdsa::KeyPair::generate();
+    let payload = key_pair.sign_payload(b"Ticket #42").to_bytes();
+    assert_eq!(key_pair.sign_payload(b"Ticket #42").to_bytes(), payload);
+    assert_ne!(key_pair.sign_payload(b"Ticket #43").to_bytes(), payload);
+}
+
+#[test]
+fn verifies_test_vector() {
+    assert_eq!(
+        ecdsa::verify(&hex(PUBLIC_KEY), &hex(SIGNATURE), b"sample"),
+        Ok(true)
+    );
+    assert_eq!(
+        ecdsa::verify(&hex(PUBLIC_KEY), &negate_s(&hex(SIGNATURE)), b"sample"),
+        Ok(true)
+    );
+    assert_eq!(
+        ecdsa::verify(&hex(PUBLIC_KEY), &hex(SIGNATURE), b"test"),
+        Ok(false)
+    );
+}
+
+#[test]
+fn converts_der_signatures() {
+    let der = hex(&format!(
+        "3046022100{}022100{}",
+        &SIGNATURE[..64],
+        &SIGNATURE[64..]
+    ));
+    assert_eq!(
+        crypto::raw_to_der_signature(&hex(SIGNATURE)),
+        Ok(der.clone())
+    );
+    assert_eq!(crypto::der_to_raw_signature(&der), Ok(hex(SIGNATURE)));
+    assert_eq!(
+        SignatureEncoding::detect(&der),
+        Some(SignatureEncoding::Der)
+    );
+    assert_eq!(
+        SignatureEncoding::detect(&hex(SIGNATURE)),
+        Some(SignatureEncoding::Raw)
+    );
+    assert_eq!(SignatureEncoding::Der.to_raw(&der), Ok(hex(SIGNATURE)));
+    assert!(SignatureEncoding::Raw.to_raw(&der).is_err());
+    let payload = Payload::DerSigned {
+        signature: der.clone(),
+        data: b"sample".to_vec(),
+    };
+    assert_eq!(payload.signature_encoding(), Some(SignatureEncoding::Der));
+    assert_eq!(ecdsa::verify_payload(&hex(PUBLIC_KEY), &payload), Ok(true));
+
+    // The prefix tells the encoding, it isn't guessed from the signature.
+    let payload = Payload::DerSigned {
+        signature: hex(SIGNATURE),
+        data: b"sample".to_vec(),
+    };
+    assert!(ecdsa::verify_payload(&hex(PUBLIC_KEY), &payload).is_err());
+
+    // Small numbers are stored without leading zeros.
+    let mut raw = vec![0; payload::SIGNATURE_SIZE];
+    raw[31] = 0x01;
+    raw[63] = 0x7F;
+    let der = crypto::raw_to_der_signature(&raw).unwrap();
+    assert_eq!(der, [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x7F]);
+    assert_eq!(crypto::der_to_raw_signature(&der), Ok(raw));
+    assert!(crypto::der_to_raw_signature(&der[..7]).is_err());
+}
+
+#[test]
+fn verifies_generated_payload() {
+    let key_pair = ecdsa::KeyPair::generate();
+    let payload = Payload::parse(&key_pair.sign_payload(b"Ticket #42").to_bytes()).unwrap();
+    assert_eq!(
+        ecdsa::verify_payload(&key_pair.public_key(), &payload),
+        Ok(true)
+    );
+
+    let other = ecdsa::KeyPair::generate();
+    assert_eq!(
+        ecdsa::verify_payload(&other.public_key(), &payload),
+        Ok(false)
+    );
+}
+
+#[test]
+fn verifies_file_payload() {
+    let key_pair = ecdsa::KeyPair::generate();
+    let payload = key_pair.sign_file_payload("report.pdf", b"%PDF-1.4");
+    assert_eq!(
+        ecdsa::verify_payload(&key_pair.public_key(), &payload),
+        Ok(true)
+    );
+
+    // Renaming the file in the code breaks the signature.
+    let renamed = match payload {
+        Payload::File {
+            signature, hash, ..
+        } => Payload::File {
+            signature,
+            hash,
+            name: "invoice.pdf".to_owned(),
+        },
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        ecdsa::verify_payload(&key_pair.public_key(), &renamed),
+        Ok(false)
+    );
+}
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(matches!(
+        ecdsa::KeyPair::from_private_key(&[0; 32]),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(matches!(
+        ecdsa::verify(&[4; 65], &hex(SIGNATURE), b"sample"),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(matches!(
+        ecdsa::verify(&hex(PUBLIC_KEY), &[0; 64], b"sample"),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(matches!(
+        ecdsa::verify_payload(&hex(PUBLIC_KEY), &Payload::PublicKey(hex(PUBLIC_KEY))),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn verifies_rotation() {
+    let old = ecdsa::KeyPair::generate();
+    let new = ecdsa::KeyPair::generate();
+    let payload = Payload::parse(&old.rotation_payload(&new).to_bytes()).unwrap();
+    assert_eq!(ecdsa::verify_rotation(&payload), Ok(true));
+
+    // Anyone can sign with a new key, but not on behalf of the old one.
+    let forged = match payload {
+        Payload::Rotation {
+            old_key,
+            old_signature,
+            ..
+        } => {
+            let intruder = ecdsa::KeyPair::generate();
+            let message = payload::rotation_message(&old_key, &intruder.public_key());
+            Payload::Rotation {
+                old_signature,
+                new_signature: intruder.sign(&message),
+                old_key,
+                new_key: intruder.public_key(),
+            }
+        }
+        _ => unreachable!(),
+    };
+    assert_eq!(ecdsa::verify_rotation(&forged), Ok(false));
+
+    // Other codes are not rotations.
+    assert!(matches!(
+        ecdsa::verify_rotation(&new.sign_payload(b"data")),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn verifies_threshold() {
+    let departments: Vec<_> = (0..3).map(|_| ecdsa::KeyPair::generate()).collect();
+    let public_keys: Vec<_> = departments.iter().map(|key| key.public_key()).collect();
+
+    let mut payload = departments[0].sign_multi_payload(b"Purchase order 1234");
+    assert_eq!(
+        ecdsa::verify_threshold(&public_keys, 2, &payload),
+        Ok(false)
+    );
+
+    // Signing twice with the same key doesn't count twice.
+    departments[0].cosign(&mut payload).unwrap();
+    assert_eq!(
+        ecdsa::verify_threshold(&public_keys, 2, &payload),
+        Ok(false)
+    );
+
+    // Signatures of unknown keys are ignored.
+    ecdsa::KeyPair::generate().cosign(&mut payload).unwrap();
+    assert_eq!(
+        ecdsa::verify_threshold(&public_keys, 2, &payload),
+        Ok(false)
+    );
+
+    departments[2].cosign(&mut payload).unwrap();
+    let payload = Payload::parse(&payload.to_bytes()).unwrap();
+    assert_eq!(ecdsa::valid_signers(&public_keys, &payload), Ok(vec![0, 2]));
+    assert_eq!(ecdsa::verify_threshold(&public_keys, 2, &payload), Ok(true));
+    assert_eq!(
+        ecdsa::verify_threshold(&public_keys, 3, &payload),
+        Ok(false)
+    );
+}
+
+#[test]
+fn rejects_cosignature_as_signed_code() {
+    let key_pair = ecdsa::KeyPair::generate();
+    let payload = key_pair.sign_multi_payload(b"Purchase order 1234");
+    let (signatures, data) = match payload {
+        Payload::MultiSigned { signatures, data } => (signatures, data),
+        _ => unreachable!(),
+    };
+    let rewrapped = Payload::Signed {
+        signature: signatures[0].signature.clone(),
+        data,
+    };
+    assert_eq!(
+        ecdsa::verify_payload(&key_pair.public_key(), &rewrapped),
+        Ok(false)
+    );
+}
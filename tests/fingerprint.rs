@@ -0,0 +1,69 @@
+use qr_signing::fingerprint;
+
+const KEY: &[u8] = b"\x04public key";
+
+#[test]
+fn formats_as_hex() {
+    let text = fingerprint::to_hex(&fingerprint::fingerprint(KEY));
+    assert_eq!(text.len(), 32 * 3 - 1);
+    assert!(text.split(':').all(|byte| byte.len() == 2
+        && byte
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase())));
+}
+
+#[test]
+fn parses_hex() {
+    let fingerprint = fingerprint::fingerprint(KEY);
+    let text = fingerprint::to_hex(&fingerprint);
+    assert_eq!(fingerprint::parse_hex(&text), Some(fingerprint));
+    assert_eq!(
+        fingerprint::parse_hex(&text.replace(':', "").to_lowercase()),
+        Some(fingerprint)
+    );
+}
+
+#[test]
+fn rejects_invalid_hex() {
+    let text = fingerprint::to_hex(&fingerprint::fingerprint(KEY));
+    assert_eq!(fingerprint::parse_hex(&text[3..]), None);
+    assert_eq!(fingerprint::parse_hex(&format!("ZZ{}", &text[2..])), None);
+    assert_eq!(fingerprint::parse_hex(""), None);
+}
+
+#[test]
+fn picks_emoji_from_first_bits() {
+    let mut fingerprint = [0xff; 32];
+    fingerprint[..10]
+        .copy_from_slice(&[0x04, 0x20, 0xc4, 0x14, 0x61, 0xc0, 0x3f, 0xa2, 0x91, 0xc4]);
+    let names: Vec<&str> = fingerprint::emoji(&fingerprint)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    assert_eq!(
+        names,
+        ["Cat", "Lion", "Horse", "Unicorn", "Pig", "Elephant", "Rabbit"]
+    );
+    assert_eq!(fingerprint::short_hex(&fingerprint), "3FA2 91C4");
+    assert_eq!(
+        fingerprint::short(&fingerprint),
+        "🐱 🦁 🐎 🦄 🐷 🐘 🐰 (3FA2 91C4)"
+    );
+}
+
+#[test]
+fn uses_every_emoji() {
+    for (idx, expected) in fingerprint::EMOJI.iter().enumerate() {
+        // Every 6 bit group of the first 42 bits set to `idx`.
+        let bits = (0..fingerprint::EMOJI_COUNT).fold(0u64, |bits, _| bits << 6 | idx as u64) << 6;
+        let mut fingerprint = [0; 32];
+        fingerprint[..6].copy_from_slice(&bits.to_be_bytes()[2..]);
+        assert_eq!(
+            fingerprint::emoji(&fingerprint),
+            vec![*expected; fingerprint::EMOJI_COUNT]
+        );
+    }
+    let names: std::collections::HashSet<_> =
+        fingerprint::EMOJI.iter().map(|(_, name)| name).collect();
+    assert_eq!(names.len(), fingerprint::EMOJI.len());
+}
@@ -0,0 +1,154 @@
+use p256::{
+    ecdsa::SigningKey,
+    pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding},
+};
+use qr_signing::{
+    key_file::{self, KeyFile},
+    Error,
+};
+
+fn signing_key() -> SigningKey {
+    SigningKey::from_slice(&[0x42; 32]).unwrap()
+}
+
+fn pkcs8_der() -> Vec<u8> {
+    signing_key().to_pkcs8_der().unwrap().as_bytes().to_vec()
+}
+
+fn spki_der() -> Vec<u8> {
+    signing_key()
+        .verifying_key()
+        .to_public_key_der()
+        .unwrap()
+        .as_bytes()
+        .to_vec()
+}
+
+#[test]
+fn detects_json() {
+    let json = "\n{\"type\":\"qr_key_pair\"}";
+    assert_eq!(
+        KeyFile::parse(json.as_bytes()),
+        Ok(KeyFile::Json("{\"type\":\"qr_key_pair\"}".to_owned()))
+    );
+}
+
+#[test]
+fn detects_der() {
+    assert_eq!(
+        KeyFile::parse(&pkcs8_der()),
+        Ok(KeyFile::Der {
+            private_key: Some(pkcs8_der()),
+            public_key: None,
+        })
+    );
+    assert_eq!(
+        KeyFile::parse(&spki_der()),
+        Ok(KeyFile::Der {
+            private_key: None,
+            public_key: Some(spki_der()),
+        })
+    );
+}
+
+#[test]
+fn detects_raw_public_key() {
+    let raw = signing_key()
+        .verifying_key()
+        .to_encoded_point(false)
+        .as_bytes()
+        .to_vec();
+    assert_eq!(KeyFile::parse(&raw), Ok(KeyFile::RawPublicKey(raw.clone())));
+    let pasted = format!("  {}\n", base64::encode(&raw));
+    assert_eq!(
+        KeyFile::parse(pasted.as_bytes()),
+        Ok(KeyFile::RawPublicKey(raw))
+    );
+    let compressed = signing_key()
+        .verifying_key()
+        .to_encoded_point(true)
+        .as_bytes()
+        .to_vec();
+    assert_eq!(
+        KeyFile::parse(&compressed),
+        Ok(KeyFile::RawPublicKey(compressed))
+    );
+}
+
+#[test]
+fn detects_base64_der() {
+    assert_eq!(
+        KeyFile::parse(base64::encode(spki_der()).as_bytes()),
+        Ok(KeyFile::Der {
+            private_key: None,
+            public_key: Some(spki_der()),
+        })
+    );
+}
+
+#[test]
+fn reads_pem_with_surrounding_text() {
+    let private_pem = signing_key().to_pkcs8_pem(LineEnding::CRLF).unwrap();
+    let public_pem = signing_key()
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    let pem = format!("Bag Attributes\n{}{}", public_pem, private_pem.as_str());
+
+    assert_eq!(
+        KeyFile::parse(pem.as_bytes()),
+        Ok(KeyFile::Der {
+            private_key: Some(pkcs8_der()),
+            public_key: Some(spki_der()),
+        })
+    );
+}
+
+#[test]
+fn round_trips_pem() {
+    let pem = [
+        key_file::pem_encode(key_file::PRIVATE_KEY_LABEL, &pkcs8_der()),
+        key_file::pem_encode(key_file::PUBLIC_KEY_LABEL, &spki_der()),
+    ]
+    .concat();
+    assert!(pem.lines().all(|line| line.len() <= 64));
+    assert_eq!(
+        key_file::pem_decode(&pem),
+        Ok(vec![
+            (key_file::PRIVATE_KEY_LABEL.to_owned(), pkcs8_der()),
+            (key_file::PUBLIC_KEY_LABEL.to_owned(), spki_der()),
+        ])
+    );
+
+    // The same as p256 writes it.
+    let expected = signing_key()
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    assert_eq!(
+        key_file::pem_encode(key_file::PUBLIC_KEY_LABEL, &spki_der()),
+        expected
+    );
+}
+
+#[test]
+fn rejects_unsupported_keys() {
+    let sec1 = key_file::pem_encode("EC PRIVATE KEY", &[0x30, 0x00]);
+    assert!(matches!(
+        KeyFile::parse(sec1.as_bytes()),
+        Err(Error::UnsupportedAlgorithm(_))
+    ));
+    let certificate = key_file::pem_encode("CERTIFICATE", &[0x30, 0x00]);
+    assert!(matches!(
+        KeyFile::parse(certificate.as_bytes()),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(matches!(
+        KeyFile::parse(b"not a key"),
+        Err(Error::MalformedPayload(_))
+    ));
+    assert!(matches!(
+        KeyFile::parse(&pkcs8_der()[..20]),
+        Err(Error::MalformedPayload(_))
+    ));
+}
@@ -0,0 +1,74 @@
+use qr_signing::{
+    metadata::{self, Metadata},
+    Error,
+};
+
+#[test]
+fn round_trips_metadata() {
+    let metadata = Metadata {
+        issued_at: Some(1_714_564_800),
+        station: Some("Gate 3".to_owned()),
+        batch: Some("May".to_owned()),
+    };
+    let data = metadata.wrap(b"Ticket #42");
+    assert_eq!(
+        Metadata::split(&data),
+        Ok((metadata, b"Ticket #42".as_ref()))
+    );
+}
+
+#[test]
+fn keeps_data_without_metadata() {
+    assert_eq!(Metadata::default().wrap(b"Ticket #42"), b"Ticket #42");
+    assert_eq!(
+        Metadata::split(b"Ticket #42"),
+        Ok((Metadata::default(), b"Ticket #42".as_ref()))
+    );
+}
+
+#[test]
+fn skips_unknown_fields() {
+    let (metadata, data) = Metadata::split(b"\x1es=Gate 3\x1fx=later\x1edata").unwrap();
+    assert_eq!(metadata.station.as_deref(), Some("Gate 3"));
+    assert_eq!(data, b"data");
+}
+
+#[test]
+fn strips_separators_from_values() {
+    let metadata = Metadata {
+        station: Some("Gate\x1e3\x1f".to_owned()),
+        ..Metadata::default()
+    };
+    let (parsed, data) = Metadata::split(&metadata.wrap(b"data"))
+        .map(|(parsed, data)| (parsed, data.to_vec()))
+        .unwrap();
+    assert_eq!(parsed.station.as_deref(), Some("Gate3"));
+    assert_eq!(data, b"data");
+}
+
+#[test]
+fn rejects_broken_metadata() {
+    for data in &[
+        b"\x1et=1714564800".as_ref(),
+        b"\x1et=yesterday\x1edata",
+        b"\x1estation\x1edata",
+    ] {
+        assert!(matches!(
+            Metadata::split(data),
+            Err(Error::MalformedPayload(_))
+        ));
+    }
+}
+
+#[test]
+fn formats_timestamps() {
+    assert_eq!(metadata::format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    assert_eq!(
+        metadata::format_timestamp(1_714_564_800),
+        "2024-05-01 12:00:00 UTC"
+    );
+    assert_eq!(
+        metadata::format_timestamp(951_825_599),
+        "2000-02-29 11:59:59 UTC"
+    );
+}
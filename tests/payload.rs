@@ -0,0 +1,208 @@
+use qr_signing::{
+    payload::{self, Cosignature, HASH_SIZE, KEY_ID_SIZE, SIGNATURE_SIZE},
+    Error, Payload,
+};
+
+#[test]
+fn round_trips_public_key() {
+    let payload = Payload::PublicKey(vec![4; 65]);
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"PUB:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+}
+
+#[test]
+fn round_trips_signed_data() {
+    let payload = Payload::Signed {
+        signature: (0..SIGNATURE_SIZE as u8).collect(),
+        data: "Grüße".as_bytes().to_vec(),
+    };
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"SIGN:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+}
+
+#[test]
+fn round_trips_der_signed_data() {
+    let signature = vec![0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+    let payload = Payload::signed(signature.clone(), b"Ticket #42".to_vec()).unwrap();
+    assert_eq!(
+        payload,
+        Payload::DerSigned {
+            signature: signature.clone(),
+            data: b"Ticket #42".to_vec(),
+        }
+    );
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"SIGD:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+    assert_eq!(
+        Payload::parse(&bytes[..b"SIGD:".len() + 4]),
+        Err(Error::malformed("The signature is truncated."))
+    );
+    assert!(matches!(
+        Payload::signed(vec![0; SIGNATURE_SIZE], Vec::new()),
+        Ok(Payload::Signed { .. })
+    ));
+}
+
+#[test]
+fn round_trips_file() {
+    let payload = Payload::File {
+        signature: vec![7; SIGNATURE_SIZE],
+        hash: payload::file_hash(b"%PDF-1.4"),
+        name: "Rechnung März.pdf".to_owned(),
+    };
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"FILE:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+}
+
+#[test]
+fn signs_file_name_with_hash() {
+    let hash = payload::file_hash(b"contents");
+    let payload = Payload::File {
+        signature: vec![0; SIGNATURE_SIZE],
+        hash,
+        name: "a.txt".to_owned(),
+    };
+    assert_eq!(
+        payload.signed_message(),
+        Some([hash.as_ref(), b"a.txt"].concat())
+    );
+    assert_eq!(Payload::PublicKey(vec![4]).signed_message(), None);
+}
+
+#[test]
+fn accepts_empty_signed_data() {
+    let bytes = [b"SIGN:".as_ref(), &[0; SIGNATURE_SIZE]].concat();
+    assert_eq!(
+        Payload::parse(&bytes),
+        Ok(Payload::Signed {
+            signature: vec![0; SIGNATURE_SIZE],
+            data: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn rejects_truncated_signature() {
+    let bytes = [b"SIGN:".as_ref(), &[0; SIGNATURE_SIZE - 1]].concat();
+    assert!(matches!(
+        Payload::parse(&bytes),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn rejects_missing_public_key() {
+    assert!(matches!(
+        Payload::parse(b"PUB:"),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn rejects_unknown_prefix() {
+    assert!(matches!(
+        Payload::parse(b"https://example.com"),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn rejects_truncated_file_hash() {
+    let bytes = [b"FILE:".as_ref(), &[0; SIGNATURE_SIZE + HASH_SIZE - 1]].concat();
+    assert!(matches!(
+        Payload::parse(&bytes),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn rejects_invalid_file_name() {
+    let bytes = [b"FILE:".as_ref(), &[0; SIGNATURE_SIZE + HASH_SIZE], &[0xff]].concat();
+    assert!(matches!(
+        Payload::parse(&bytes),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn round_trips_rotation() {
+    let payload = Payload::Rotation {
+        old_key: vec![4; 65],
+        new_key: vec![2; 33],
+        old_signature: vec![1; SIGNATURE_SIZE],
+        new_signature: vec![9; SIGNATURE_SIZE],
+    };
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"ROT:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+}
+
+#[test]
+fn rejects_rotation_without_new_key() {
+    let bytes = [b"ROT:".as_ref(), &[0; 2 * SIGNATURE_SIZE], &[65], &[4; 65]].concat();
+    assert!(matches!(
+        Payload::parse(&bytes),
+        Err(Error::MalformedPayload(_))
+    ));
+}
+
+#[test]
+fn round_trips_multi_signed() {
+    let payload = Payload::MultiSigned {
+        signatures: vec![
+            Cosignature {
+                key_id: [1, 2, 3, 4],
+                signature: vec![5; SIGNATURE_SIZE],
+            },
+            Cosignature {
+                key_id: [6, 7, 8, 9],
+                signature: vec![10; SIGNATURE_SIZE],
+            },
+        ],
+        data: b"Contract #7".to_vec(),
+    };
+    let bytes = payload.to_bytes();
+    assert!(bytes.starts_with(b"MULTI:"));
+    assert_eq!(Payload::parse(&bytes), Ok(payload));
+}
+
+#[test]
+fn replaces_signature_of_same_key() {
+    let mut payload = Payload::MultiSigned {
+        signatures: Vec::new(),
+        data: b"data".to_vec(),
+    };
+    for signature in &[1, 2] {
+        payload
+            .add_signature(Cosignature {
+                key_id: [1; KEY_ID_SIZE],
+                signature: vec![*signature; SIGNATURE_SIZE],
+            })
+            .unwrap();
+    }
+    match payload {
+        Payload::MultiSigned { signatures, .. } => {
+            assert_eq!(signatures.len(), 1);
+            assert_eq!(signatures[0].signature, vec![2; SIGNATURE_SIZE]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn rejects_truncated_cosignatures() {
+    let bytes = [
+        b"MULTI:".as_ref(),
+        &[2],
+        &[0; KEY_ID_SIZE + SIGNATURE_SIZE + 10],
+    ]
+    .concat();
+    assert!(matches!(
+        Payload::parse(&bytes),
+        Err(Error::MalformedPayload(_))
+    ));
+}
@@ -0,0 +1,58 @@
+use qr_signing::{payload::SIGNATURE_SIZE, payload_url, Error, Payload};
+
+#[test]
+fn escapes_base45_in_fragment() {
+    // Encodes as `%69 VD92EX0`.
+    let url = payload_url::to_url("https://verify.example/", b"Hello!!");
+    assert_eq!(url, "https://verify.example/#%2569%20VD92EX0");
+    assert_eq!(payload_url::from_url(&url), Some(Ok(b"Hello!!".to_vec())));
+    assert_eq!(
+        payload_url::decode_fragment("#%2569%20VD92EX0"),
+        Ok(b"Hello!!".to_vec())
+    );
+}
+
+#[test]
+fn replaces_existing_fragment() {
+    assert_eq!(
+        payload_url::to_url("https://verify.example/app?x=1#old", b"AB"),
+        "https://verify.example/app?x=1#BB8"
+    );
+    assert_eq!(payload_url::from_url("https://verify.example/"), None);
+}
+
+#[test]
+fn recognizes_payload_fragments() {
+    let payload = Payload::PublicKey(vec![0x02; 33]).to_bytes();
+    let url = payload_url::to_url("https://verify.example/", &payload);
+    assert!(payload_url::is_payload_fragment(
+        &url[url.find('#').unwrap()..]
+    ));
+    // Broken payloads are still recognized, to report them.
+    let truncated = payload_url::to_url("", &payload[..8]);
+    assert!(payload_url::is_payload_fragment(&truncated));
+    for fragment in &["", "#", "#top", "#TOP", "#BB8"] {
+        assert!(!payload_url::is_payload_fragment(fragment));
+    }
+}
+
+#[test]
+fn rejects_invalid_escapes() {
+    for fragment in &["%2", "%G0", "BB8%"] {
+        assert!(matches!(
+            payload_url::decode_fragment(fragment),
+            Err(Error::MalformedPayload(_))
+        ));
+    }
+}
+
+#[test]
+fn parses_scanned_urls() {
+    let payload = Payload::Signed {
+        signature: vec![0x25; SIGNATURE_SIZE],
+        data: b"Ticket #42".to_vec(),
+    };
+    let url = payload_url::to_url("https://verify.example/", &payload.to_bytes());
+    assert!(!url[url.find('#').unwrap() + 1..].contains(' '));
+    assert_eq!(Payload::parse(url.as_bytes()), Ok(payload));
+}
@@ -0,0 +1,84 @@
+use qr_signing::{crypto, ecdsa, webauthn, Payload};
+
+/// Signs like a security key would, standing in for a real authenticator.
+struct SoftwareAuthenticator {
+    key_pair: ecdsa::KeyPair,
+    counter: u32,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        Self {
+            key_pair: ecdsa::KeyPair::generate(),
+            counter: 0,
+        }
+    }
+
+    /// Returns an assertion over `data` as the browser would pack it.
+    fn sign(&mut self, data: &[u8], flags: u8) -> Payload {
+        self.counter += 1;
+        let authenticator_data = [
+            &hmac_sha256::Hash::hash(b"example.org")[..],
+            &[flags],
+            &self.counter.to_be_bytes(),
+        ]
+        .concat();
+        let client_data = format!(
+            r#"{},"origin":"https://example.org","crossOrigin":false}}"#,
+            webauthn::client_data_prefix(data)
+        )
+        .into_bytes();
+        let signature = self
+            .key_pair
+            .sign(&webauthn::signed_message(&authenticator_data, &client_data));
+        Payload::WebAuthn {
+            signature: crypto::raw_to_der_signature(&signature).unwrap(),
+            authenticator_data,
+            client_data,
+            data: data.to_vec(),
+        }
+    }
+}
+
+#[test]
+fn verifies_assertions() {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let payload = authenticator.sign(b"hello", 0x01);
+    let parsed = Payload::parse(&payload.to_bytes()).unwrap();
+    assert_eq!(parsed, payload);
+    let public_key = authenticator.key_pair.public_key();
+    assert_eq!(ecdsa::verify_payload(&public_key, &parsed), Ok(true));
+    let other_key = ecdsa::KeyPair::generate().public_key();
+    assert_eq!(ecdsa::verify_payload(&other_key, &parsed), Ok(false));
+}
+
+#[test]
+fn rejects_assertions_for_other_data() {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let mut payload = authenticator.sign(b"hello", 0x01);
+    if let Payload::WebAuthn { data, .. } = &mut payload {
+        *data = b"world".to_vec();
+    }
+    assert!(Payload::parse(&payload.to_bytes()).is_err());
+    let public_key = authenticator.key_pair.public_key();
+    assert!(ecdsa::verify_payload(&public_key, &payload).is_err());
+}
+
+#[test]
+fn rejects_assertions_without_user_presence() {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let payload = authenticator.sign(b"hello", 0x00);
+    assert!(Payload::parse(&payload.to_bytes()).is_err());
+    let public_key = authenticator.key_pair.public_key();
+    assert!(ecdsa::verify_payload(&public_key, &payload).is_err());
+}
+
+#[test]
+fn rejects_truncated_assertions() {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let bytes = authenticator.sign(b"hello", 0x01).to_bytes();
+    let data_start = bytes.len() - b"hello".len();
+    for size in [10, 80, 120, data_start - 1] {
+        assert!(Payload::parse(&bytes[..size]).is_err(), "size {}", size);
+    }
+}
@@ -1,13 +1,57 @@
+const crypto = require("crypto");
+const fs = require("fs");
 const path = require("path");
 const CopyPlugin = require("copy-webpack-plugin");
 const WasmPackPlugin = require("@wasm-tool/wasm-pack-plugin");
 
 const dist = path.resolve(__dirname, "dist");
 
-module.exports = {
+// Builds with RUST_DECODER set decode QR codes in wasm and leave out
+// html5-qrcode, see the `rust-decoder` feature in Cargo.toml.
+const rustDecoder = Boolean(process.env.RUST_DECODER);
+const html5QrcodeScript = /\s*<script src="html5-qrcode.min.js"><\/script>/;
+
+function listFiles(dir) {
+  return fs.readdirSync(dir, { withFileTypes: true }).flatMap(entry => {
+    const file = path.join(dir, entry.name);
+    return entry.isDirectory() ? listFiles(file) : [file];
+  });
+}
+
+// Writes the service worker from js/sw.js with every file of the build in its
+// precache list, versioned by the hash of their contents. Runs after both
+// bundles are written; the dev server keeps files in memory and gets none.
+class OfflinePlugin {
+  apply(compiler) {
+    compiler.hooks.afterEmit.tap("OfflinePlugin", () => {
+      if (!fs.existsSync(path.join(dist, "index.js"))) {
+        return;
+      }
+      const files = listFiles(dist)
+        .filter(file => !file.endsWith(".map") && path.basename(file) !== "sw.js")
+        .sort();
+      const hash = crypto.createHash("sha256");
+      for (const file of files) {
+        hash.update(file).update(fs.readFileSync(file));
+      }
+      const precache = ["./"].concat(files.map(file => path.relative(dist, file).split(path.sep).join("/")));
+      const template = fs.readFileSync(path.resolve(__dirname, "js/sw.js"), "utf8");
+      fs.writeFileSync(
+        path.join(dist, "sw.js"),
+        template
+          .replace("__PRECACHE__", JSON.stringify(precache))
+          .replace("__VERSION__", JSON.stringify(hash.digest("hex").slice(0, 16)))
+      );
+    });
+  }
+}
+
+const app = {
+  name: "app",
   mode: "production",
   entry: {
-    index: "./js/index.js"
+    index: "./js/index.js",
+    "qr-verify": "./js/qr-verify.js"
   },
   output: {
     path: dist,
@@ -20,7 +64,15 @@ module.exports = {
   plugins: [
     new CopyPlugin({
       patterns: [
-        path.resolve(__dirname, "static"),
+        {
+          from: path.resolve(__dirname, "static"),
+          globOptions: { ignore: rustDecoder ? ["**/html5-qrcode.min.js"] : [] },
+          transform(content, file) {
+            return rustDecoder && path.basename(file) === "index.html"
+              ? content.toString().replace(html5QrcodeScript, "")
+              : content;
+          },
+        },
         { from: path.resolve(__dirname, "node_modules/@mdi/font/fonts"), to: "fonts" },
         { from: path.resolve(__dirname, "node_modules/@mdi/font/css"), to: "css" },
       ],
@@ -28,6 +80,30 @@ module.exports = {
 
     new WasmPackPlugin({
       crateDirectory: __dirname,
+      extraArgs: rustDecoder ? "-- --features rust-decoder" : "",
     }),
   ]
 };
+
+// The batch signer runs the same wasm module in a web worker, which needs its
+// own bundle because chunks are loaded differently there.
+const worker = {
+  name: "worker",
+  dependencies: ["app"],
+  mode: "production",
+  target: "webworker",
+  entry: {
+    worker: "./js/worker.js"
+  },
+  output: {
+    path: dist,
+    filename: "[name].js",
+    chunkFilename: "worker.[id].js"
+  },
+  devtool: 'source-map',
+  plugins: [
+    new OfflinePlugin(),
+  ],
+};
+
+module.exports = [app, worker];